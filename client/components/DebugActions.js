@@ -38,11 +38,63 @@ export default function DebugActions({ player }) {
         )
         break
 
+      case "ChooseStartingPlayer":
+        // Drawing first lets the next player in turn order play first.
+        const nextPlayer =
+          game.players().find((other) => other.id > player) ||
+          game.players()[0]
+        const chooseStartingPlayer = (startingPlayer) => () =>
+          doAction(player, {
+            type: "ChooseStartingPlayer",
+            player: startingPlayer,
+          })
+        actions.push(
+          <DebugButton key="play" onClick={chooseStartingPlayer(player)}>
+            Play First
+          </DebugButton>,
+          <DebugButton
+            key="draw"
+            onClick={chooseStartingPlayer(nextPlayer.id)}
+          >
+            Draw First
+          </DebugButton>
+        )
+        break
+
+      case "Mulligan":
+        // TODO: Let the player pick which cards go on the bottom of their
+        // library instead of using the first legal choice.
+        const keep = game
+          .legalActions(player)
+          .find((action) => action.type === "KeepHand")
+        if (keep != null) {
+          actions.push(
+            <DebugButton key="keep" onClick={() => doAction(player, keep)}>
+              Keep Hand
+            </DebugButton>
+          )
+        }
+
+        const mulligan = () => doAction(player, { type: "Mulligan" })
+        actions.push(
+          <DebugButton key="mulligan" onClick={mulligan}>
+            Mulligan
+          </DebugButton>
+        )
+        break
+
       case "ChooseAttackers":
         const confirmAttackers = () => {
+          // Every creature attacks the next opponent in turn order.
+          const opponent = game
+            .players()
+            .find((other) => other.id > player) || game.players()[0]
           doAction(player, {
             type: "ChooseAttackers",
-            attackers: combat.attackers,
+            attackers: combat.attackers.map((attacker) => ({
+              attacker,
+              target: { Player: opponent.id },
+            })),
           })
           combat.setAttackers([])
         }
@@ -70,7 +122,7 @@ export default function DebugActions({ player }) {
 
       case "SpellManaAbilities":
         const spell = game
-          .objectsInZone("Stack")
+          .objectsInZone("Stack", { type: "Spectator" })
           .find(
             (object) =>
               object.controller === player && object.incompleteSpell != null
@@ -85,6 +137,18 @@ export default function DebugActions({ player }) {
               spell: spell.entity,
             })
 
+          const autoPay = () =>
+            doAction(player, {
+              type: "AutoPaySpell",
+              spell: spell.entity,
+            })
+
+          actions.push(
+            <DebugButton key="auto-pay" onClick={autoPay}>
+              Auto Pay
+            </DebugButton>
+          )
+
           actions.push(
             <DebugButton key="finish-casting" onClick={finishCasting}>
               Finish Casting
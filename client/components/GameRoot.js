@@ -7,7 +7,12 @@ export default function GameRoot({ game, children }) {
   const [objectDb, _updateObjectDb] = useState(game.objectDb())
 
   const doAction = (player, action) => {
-    game.doAction(player, action)
+    try {
+      game.doAction(player, action)
+    } catch (error) {
+      console.warn(`Action ${action.type} failed: ${error.message}`, error)
+    }
+
     rerender(num + 1)
   }
 
@@ -27,7 +27,8 @@ const ManaSymbol = styled.img`
 `
 
 const Mana = ({ mana, onClick }) => {
-  const symbol = symbolMap[mana.color]
+  // TODO: A {C} symbol for colorless mana.
+  const symbol = mana.color != null ? symbolMap[mana.color] : mana1
 
   return (
     <a href="#" onClick={onClick}>
@@ -39,8 +40,8 @@ const Mana = ({ mana, onClick }) => {
 export default function ManaPool({ pool, onClick }) {
   return (
     <StyledManaPool>
-      {pool.map((mana, i) => (
-        <Mana key={i} mana={mana} onClick={() => onClick && onClick(i)} />
+      {pool.map(({ id, mana }) => (
+        <Mana key={id} mana={mana} onClick={() => onClick && onClick(id)} />
       ))}
     </StyledManaPool>
   )
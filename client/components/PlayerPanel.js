@@ -68,9 +68,9 @@ export default function PlayerPanel({
   profilePicture,
 }) {
   const { game, doAction } = useContext(GameContext)
-  const clickMana = (i) => {
+  const clickMana = (mana) => {
     const incompleteSpell = game
-      .objectsInZone("Stack")
+      .objectsInZone("Stack", { type: "Spectator" })
       .find(
         (object) =>
           object.controller === player.id && object.incompleteSpell != null
@@ -83,7 +83,7 @@ export default function PlayerPanel({
     doAction(player.id, {
       type: "PayIncompleteSpellMana",
       spell: incompleteSpell.entity,
-      mana: i,
+      mana,
     })
   }
 
@@ -68,25 +68,32 @@ function Main() {
   const state = game.state()
 
   const priority = getPriority(game)
-  const battlefield = game.objectsInZone("Battlefield")
+  const battlefield = game.objectsInZone("Battlefield", { type: "Spectator" })
   const players = game.players().map((player, index) => {
-    const hand = game.objectsInZone({ Hand: player.id })
-    const library = game.objectsInZone({ Library: player.id })
+    // Every player shares the screen, so each hand is shown as its owner
+    // sees it.
+    const hand = game.objectsInZone(
+      { Hand: player.id },
+      { type: "Player", player: player.id }
+    )
     const top = index === 0
 
+    // TODO: Let the player choose X for spells with {X} in their cost instead
+    // of casting them with the first legal choice, which is 0.
     const playCard = (object) => {
-      if (object.types.includes("Land")) {
-        doAction(player.id, {
-          type: "PlayLand",
-          card: object.entity,
-        })
-      } else if (object.types.includes("Creature")) {
-        doAction(player.id, {
-          type: "StartCastingSpell",
-          spell: object.entity,
-        })
+      const action = game
+        .legalActions(player.id)
+        .find(
+          (action) =>
+            (action.type === "PlayLand" && action.card === object.entity) ||
+            (action.type === "StartCastingSpell" &&
+              action.spell === object.entity)
+        )
+
+      if (action != null) {
+        doAction(player.id, action)
       } else {
-        console.warn("Can't play this object yet:", object)
+        console.warn("Can't play this object right now:", object)
       }
     }
 
@@ -173,7 +180,7 @@ function Main() {
         <PlayerPanel
           player={player}
           priority={priority === player.id}
-          libraryCount={library.length}
+          libraryCount={player.librarySize}
           profilePicture={profilePictures[index]}
         />
 
@@ -186,7 +193,7 @@ function Main() {
     )
   })
 
-  const stack = game.objectsInZone("Stack")
+  const stack = game.objectsInZone("Stack", { type: "Spectator" })
 
   return (
     <GameContainer>
@@ -1,12 +1,14 @@
 mod ffi;
 
 use mtg_engine::{
-    action::PlayerAction,
+    action::{ActionError, PlayerAction},
     components::{Card, IncompleteSpell, Object, Permanent},
-    game::Game,
+    cost::{ManaColor, ManaCost},
+    deck::Deck,
+    game::{Game, GameConfig, Viewer},
     hecs::Entity,
     ident::Ident,
-    mana_pool::ManaPool,
+    mana_pool::{ManaPool, UnspentManaEffect, UnspentManaModification},
     object_db::{CardId, ObjectDb},
     player::PlayerId,
     pt::PtCharacteristic,
@@ -32,10 +34,39 @@ impl JsGame {
     #[wasm_bindgen(constructor)]
     pub fn new() -> Self {
         Self {
-            inner: sample_game(),
+            inner: sample_game(ObjectDb::load()).unwrap(),
         }
     }
 
+    /// Create the sample game with cards from a database loaded at runtime,
+    /// which must include the cards in the sample decks.
+    #[wasm_bindgen(js_name = "withObjectDb")]
+    pub fn with_object_db(object_db: &JsObjectDb) -> Result<JsGame, JsValue> {
+        let inner = sample_game(object_db.inner.clone())?;
+        Ok(Self { inner })
+    }
+
+    /// Load a game saved with `save`.
+    pub fn load(source: &str) -> Result<JsGame, JsValue> {
+        let inner = Game::load(source).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Load a game saved with `save` that was created with `withObjectDb`.
+    #[wasm_bindgen(js_name = "loadWithObjectDb")]
+    pub fn load_with_object_db(source: &str, object_db: &JsObjectDb) -> Result<JsGame, JsValue> {
+        let inner = Game::load_with_object_db(source, object_db.inner.clone())
+            .map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Save the entire game as JSON.
+    pub fn save(&self) -> Result<String, JsValue> {
+        self.inner
+            .save()
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
     #[wasm_bindgen(js_name = "objectDb")]
     pub fn object_db(&self) -> JsObjectDb {
         JsObjectDb {
@@ -48,8 +79,30 @@ impl JsGame {
         let player: PlayerId = ffi::from_js(player)?;
         let action: PlayerAction = ffi::from_js(action)?;
 
-        self.inner.do_action(player, action);
-        Ok(())
+        self.inner.do_action(player, action).map_err(|error| {
+            let error = JsActionError {
+                rule: error.rule(),
+                message: error.to_string(),
+                error,
+            };
+
+            ffi::to_js(&error).unwrap_or_else(|err| err)
+        })
+    }
+
+    #[wasm_bindgen(js_name = "legalActions")]
+    pub fn legal_actions(&self, player: JsValue) -> Result<JsValue, JsValue> {
+        let player: PlayerId = ffi::from_js(player)?;
+        ffi::to_js(&self.inner.legal_actions(player))
+    }
+
+    /// Take every event that has happened since the last call. Events aren't
+    /// redacted, so they name cards moving in and out of hidden zones, like
+    /// the card each player draws. Only available in debug builds.
+    #[cfg(debug_assertions)]
+    #[wasm_bindgen(js_name = "debugDrainEvents")]
+    pub fn debug_drain_events(&mut self) -> Result<JsValue, JsValue> {
+        ffi::to_js(&self.inner.drain_events())
     }
 
     pub fn players(&self) -> Result<JsValue, JsValue> {
@@ -57,27 +110,41 @@ impl JsGame {
             .inner
             .players()
             .iter()
-            .filter_map(|player| {
-                Some(JsPlayer {
-                    id: player.id,
-                    name: player.name.to_owned(),
-                    life: player.life as i32,
-                    lands_played_this_turn: player.lands_played_this_turn,
-                    mana_pool: player.mana_pool.clone(),
-                })
+            .map(|player| JsPlayer {
+                id: player.id,
+                name: player.name.to_owned(),
+                life: player.life as i32,
+                lands_played_this_turn: player.lands_played_this_turn,
+                mana_pool: player.mana_pool.clone(),
+                library_size: self
+                    .inner
+                    .zone(ZoneId::Library(player.id))
+                    .map_or(0, |library| library.members().len()),
             })
             .collect::<Vec<_>>();
 
         ffi::to_js(&players)
     }
 
+    /// The game as seen by a player or spectator, with hidden information
+    /// left out.
+    #[wasm_bindgen(js_name = "playerView")]
+    pub fn player_view(&self, viewer: JsValue) -> Result<JsValue, JsValue> {
+        let viewer: Viewer = ffi::from_js(viewer)?;
+        ffi::to_js(&self.inner.view(viewer))
+    }
+
+    /// The objects in a zone that a player or spectator can see, like in
+    /// `playerView`.
     #[wasm_bindgen(js_name = "objectsInZone")]
-    pub fn objects_in_zone(&self, zone: JsValue) -> Result<JsValue, JsValue> {
+    pub fn objects_in_zone(&self, zone: JsValue, viewer: JsValue) -> Result<JsValue, JsValue> {
         let zone_id: ZoneId = ffi::from_js(zone)?;
+        let viewer: Viewer = ffi::from_js(viewer)?;
 
         let entities = self.inner.zone(zone_id).unwrap().members();
         let output = entities
-            .into_iter()
+            .iter()
+            .filter(|&&entity| self.inner.can_see(viewer, entity))
             .filter_map(|&entity| {
                 let entity_ref = self.inner.world().entity(entity).ok()?;
                 let object = entity_ref.get::<Object>()?;
@@ -89,6 +156,12 @@ impl JsGame {
                     supertypes: object.supertypes.clone(),
                     subtypes: object.subtypes.clone(),
                     pt: object.pt,
+                    mana_cost: object.mana_cost.as_ref().map(ToString::to_string),
+                    colors: object
+                        .mana_cost
+                        .as_ref()
+                        .map(ManaCost::colors)
+                        .unwrap_or_default(),
                     zone: object.zone,
                     owner: object.owner,
                     controller: object.controller,
@@ -114,6 +187,12 @@ impl JsGame {
     }
 }
 
+impl Default for JsGame {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[wasm_bindgen(js_name = "ObjectDb")]
 pub struct JsObjectDb {
     inner: ObjectDb,
@@ -121,6 +200,22 @@ pub struct JsObjectDb {
 
 #[wasm_bindgen(js_class = "ObjectDb")]
 impl JsObjectDb {
+    /// Load cards from a JSON list of card descriptors, like ones fetched at
+    /// runtime.
+    #[wasm_bindgen(js_name = "fromJson")]
+    pub fn from_json(source: &str) -> Result<JsObjectDb, JsValue> {
+        let inner =
+            ObjectDb::from_json(source).map_err(|err| JsValue::from_str(&err.to_string()))?;
+        Ok(Self { inner })
+    }
+
+    /// Add the cards from another database, like another set.
+    pub fn merge(&mut self, other: &JsObjectDb) -> Result<(), JsValue> {
+        self.inner
+            .merge(other.inner.clone())
+            .map_err(|err| JsValue::from_str(&err.to_string()))
+    }
+
     #[wasm_bindgen(js_name = "cardId")]
     pub fn card_id(&self, name: &str) -> Option<u32> {
         self.inner.card_id(name).map(|id| id.0)
@@ -144,6 +239,16 @@ pub struct JsPlayer {
     pub life: i32,
     pub lands_played_this_turn: u32,
     pub mana_pool: ManaPool,
+    pub library_size: usize,
+}
+
+/// Thrown to JS when an action passed to `doAction` cannot be taken.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JsActionError {
+    pub rule: &'static str,
+    pub message: String,
+    pub error: ActionError,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -156,6 +261,12 @@ pub struct JsObject {
     pub supertypes: Vec<CardSupertype>,
     pub subtypes: Vec<CardSubtype>,
     pub pt: Option<PtCharacteristic>,
+
+    /// The object's mana cost in the usual notation, like `{1}{G}`.
+    pub mana_cost: Option<String>,
+
+    /// The colors of the object's mana cost, for picking mana symbols.
+    pub colors: Vec<ManaColor>,
     pub zone: ZoneId,
     pub owner: PlayerId,
     pub controller: Option<PlayerId>,
@@ -165,37 +276,38 @@ pub struct JsObject {
     pub incomplete_spell: Option<IncompleteSpell>,
 }
 
-pub fn sample_game() -> Game {
-    let mut game = Game::new();
-
-    let forest = game.object_db().card_id("Forest").unwrap();
-    let bear = game.object_db().card_id("Grizzly Bears").unwrap();
+static SAMPLE_DECK: &str = "\
+15 Forest
+25 Grizzly Bears
+";
 
-    let players: Vec<_> = game.players().iter().map(|player| player.id).collect();
-
-    for player in players {
-        // each player gets a nice 40 card deck
-        for _ in 0..15 {
-            game.create_card(forest, ZoneId::Library(player), player)
-                .unwrap();
-        }
-
-        for _ in 0..25 {
-            game.create_card(bear, ZoneId::Library(player), player)
-                .unwrap();
-        }
+pub fn sample_game(object_db: ObjectDb) -> Result<Game, JsValue> {
+    // each player gets a nice 40 card deck
+    let deck =
+        Deck::parse(SAMPLE_DECK, &object_db).map_err(|err| JsValue::from_str(&err.to_string()))?;
 
-        game.shuffle_libraries();
+    let mut config = GameConfig::default();
+    for player in &mut config.players {
+        player.deck = deck.main_cards();
+    }
 
-        for _ in 0..7 {
-            let card = {
-                let members = game.zone(ZoneId::Library(player)).unwrap().members();
+    let mut game = Game::with_object_db(config, object_db)
+        .map_err(|err| JsValue::from_str(&err.to_string()))?;
 
-                members[members.len() - 1]
-            };
-            game.move_object_to_zone(card, ZoneId::Hand(player));
+    // Lands can't be tapped for mana yet, so give each player some to cast
+    // spells with. Mana pools would empty before the first main phase, so
+    // each player's mana is kept like with Upwelling until the first turn
+    // ends, and then empties as usual.
+    let players: Vec<_> = game.players().iter().map(|player| player.id).collect();
+    for player in players {
+        game.add_unspent_mana_effect_until_end_of_turn(UnspentManaEffect {
+            player,
+            modification: UnspentManaModification::Keep,
+        });
+        for color in [ManaColor::Green, ManaColor::Red, ManaColor::Green] {
+            game.add_mana(player, color);
         }
     }
 
-    game
+    Ok(game)
 }
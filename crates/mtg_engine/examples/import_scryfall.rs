@@ -0,0 +1,56 @@
+//! Convert a downloaded Scryfall bulk data file into card data for the engine.
+//!
+//! ```text
+//! cargo run --example import_scryfall -- oracle-cards.json cards.json
+//! ```
+//!
+//! Cards that couldn't be converted and cards whose abilities aren't modeled
+//! are listed on stderr.
+
+use std::env;
+use std::fs::File;
+use std::io::BufWriter;
+use std::process;
+
+use mtg_engine::object_db::scryfall;
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (input, output) = match args.as_slice() {
+        [input, output] => (input, output),
+        _ => {
+            eprintln!("usage: import_scryfall <scryfall bulk data> <output>");
+            process::exit(2);
+        }
+    };
+
+    let import = match scryfall::import_path(input) {
+        Ok(import) => import,
+        Err(err) => {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+    };
+
+    for error in &import.errors {
+        eprintln!("skipped {}", error);
+    }
+
+    for card in &import.unmodeled {
+        eprintln!("abilities not modeled: {}", card.name);
+    }
+
+    let file = File::create(output).unwrap_or_else(|err| {
+        eprintln!("could not create {}: {}", output, err);
+        process::exit(1);
+    });
+    serde_json::to_writer_pretty(BufWriter::new(file), &import.cards).unwrap();
+
+    let modeled = import.cards.len() - import.unmodeled.len();
+    eprintln!(
+        "imported {} cards, {} with every ability modeled, and skipped {}",
+        import.cards.len(),
+        modeled,
+        import.errors.len()
+    );
+}
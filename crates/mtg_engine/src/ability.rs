@@ -0,0 +1,31 @@
+//! 113. Abilities
+
+use serde::{Deserialize, Serialize};
+
+use crate::effect::Effect;
+
+/// 714.2b A chapter symbol followed by an ability is a triggered ability
+///        called a chapter ability. The text “[N]—[Effect]” means “When one
+///        or more lore counters are put onto this Saga, if the number of lore
+///        counters on it was less than [N] and became at least [N], [Effect].”
+///
+/// Abilities with more than one chapter symbol, like “I, II—[Effect]”, list
+/// each of their chapter numbers in `chapters`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChapterAbility {
+    pub chapters: Vec<u32>,
+    pub effects: Vec<Effect>,
+}
+
+/// 103.4b If an effect allows a player to perform an action “any time [that
+///        player] could mulligan,” the player may perform that action at a
+///        time they would declare whether or not they will take a mulligan.
+///        This need not be in the first round of mulligans. [...]
+///
+/// The ability can be used while the card is in its owner's hand, like Serum
+/// Powder's “Any time you could mulligan and Serum Powder is in your hand, you
+/// may exile all the cards from your hand, then draw that many cards.”
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MulliganAbility {
+    pub effects: Vec<Effect>,
+}
@@ -1,10 +1,15 @@
+use std::fmt;
+
 use hecs::Entity;
 use serde::{Deserialize, Serialize};
 
+use crate::cost::MAX_GENERIC_MANA;
 use crate::mana_pool::ManaId;
+use crate::player::PlayerId;
+use crate::target::Target;
 
 /// Describes an action that a player can take in the game.
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum PlayerAction {
     /// 104.3a A player can concede the game at any time. A player who concedes
@@ -13,12 +18,54 @@ pub enum PlayerAction {
 
     PassPriority,
 
+    /// 103.1. At the start of a game, the players determine which one of them
+    ///        will choose who takes the first turn. [...]
+    ///
+    /// Choosing themselves is choosing to play first, and choosing another
+    /// player is choosing to draw.
+    ChooseStartingPlayer {
+        player: PlayerId,
+    },
+
+    /// 103.4. [...] A player who is dissatisfied with their initial hand may
+    ///        take a mulligan. [...]
+    Mulligan,
+
+    /// Keep the current hand as the player's opening hand, putting the chosen
+    /// cards on the bottom of their library one at a time, so that the last
+    /// one chosen ends up on the very bottom.
+    ///
+    /// 103.4. [...] Once a player chooses not to take a mulligan, that player
+    ///        puts a number of cards from their hand on the bottom of their
+    ///        library in any order equal to the number of times that player
+    ///        took a mulligan. [...]
+    KeepHand {
+        bottom: Vec<Entity>,
+    },
+
+    /// 103.4b If an effect allows a player to perform an action “any time
+    ///        [that player] could mulligan,” the player may perform that action
+    ///        at a time they would declare whether or not they will take a
+    ///        mulligan. [...] If the player performs the action, they then
+    ///        declare whether they will take a mulligan.
+    UseMulliganAbility {
+        card: Entity,
+    },
+
     ChooseAttackers {
-        attackers: Vec<Entity>,
+        attackers: Vec<AttackDeclaration>,
     },
 
     ChooseBlockers {
-        blockers: Vec<Entity>,
+        blockers: Vec<BlockDeclaration>,
+    },
+
+    /// 514.1. First, if the active player’s hand contains more cards than
+    ///        their maximum hand size (normally seven), they discard enough
+    ///        cards to reduce their hand size to that number. This turn-based
+    ///        action doesn’t use the stack.
+    DiscardToHandSize {
+        cards: Vec<Entity>,
     },
 
     /// 116.2a Playing a land is a special action. To play a land, a player puts
@@ -36,25 +83,80 @@ pub enum PlayerAction {
     ///        time they have priority and the stack is empty.
     StartCastingSpell {
         spell: Entity,
+
+        /// 601.2b [...] If the spell has a variable cost that will be paid as
+        ///        it’s being cast (such as an {X} in its mana cost; see rule
+        ///        107.3), the player announces the value of that variable.
+        ///        [...]
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        x: Option<u32>,
     },
 
-    /// Pay part of the mana cost of an incomplete spell.
+    /// Pay part of the mana cost of an incomplete spell. The mana pays for the
+    /// most specific unpaid part of the cost that it can, like a colored mana
+    /// symbol before generic mana.
     PayIncompleteSpellMana {
         spell: Entity,
         mana: ManaId,
     },
 
+    /// Pay 2 life for an unpaid Phyrexian mana symbol in the cost of an
+    /// incomplete spell. See rule 107.4f.
+    PayIncompleteSpellLife {
+        spell: Entity,
+    },
+
+    /// Pay the rest of the mana cost of an incomplete spell with mana from the
+    /// player's mana pool, choosing mana that pays for every part of the cost
+    /// if there is any. Mana and life that were already paid stay paid, and
+    /// Phyrexian mana symbols are only paid for with mana.
+    AutoPaySpell {
+        spell: Entity,
+    },
+
     FinishCastingSpell {
         spell: Entity,
     },
     CancelCastingSpell {
         spell: Entity,
     },
+
+    /// 616.1. If two or more replacement and/or prevention effects are
+    ///        attempting to modify the way an event affects an object or
+    ///        player, the affected object’s controller (or its owner if it has
+    ///        no controller) or the affected player chooses one to apply.
+    ChooseReplacementEffect {
+        effect: Entity,
+    },
     // 117.1b A player may activate an activated ability any time they have
     //        priority.
     // TODO: Rules 116.2b—116.2i
 }
 
+/// 508.1a The active player chooses which creatures that they control, if any,
+///        will attack.
+///
+/// 508.1b If the defending player controls any planeswalkers, or the game
+///        allows the active player to attack multiple other players, the
+///        active player announces which player or planeswalker each of the
+///        chosen creatures is attacking.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttackDeclaration {
+    pub attacker: Entity,
+    pub target: Target,
+}
+
+/// 509.1a The defending player chooses which creatures they control, if any,
+///        will block. The chosen creatures must be untapped. For each of the
+///        chosen creatures, the defending player chooses one creature for it
+///        to block that’s attacking that player or a planeswalker that player
+///        controls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockDeclaration {
+    pub blocker: Entity,
+    pub attacker: Entity,
+}
+
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum PlayerActionCategory {
     /// A player has priority and can start to take an action. In some steps
@@ -62,9 +164,21 @@ pub enum PlayerActionCategory {
     /// priority.
     Priority,
 
+    /// The player chosen at random at the start of the game chooses who takes
+    /// the first turn. See rule 103.1.
+    ChooseStartingPlayer,
+
+    /// A player declares whether they will take a mulligan or keep their hand
+    /// before the first turn begins. See rule 103.4.
+    Mulligan,
+
     ChooseAttackers,
     ChooseBlockers,
 
+    /// The active player must choose cards to discard during their cleanup
+    /// step because their hand is larger than their maximum hand size.
+    DiscardToHandSize,
+
     /// A player is in the window where they can activate mana abilities to pay
     /// for a spell.
     ///
@@ -76,4 +190,323 @@ pub enum PlayerActionCategory {
 
     /// A player is paying the costs for a spell.
     SpellPayingCost,
+
+    /// A player must choose which of several replacement effects to apply to
+    /// an event first. See rule 616.1.
+    ChooseReplacementEffect {
+        options: Vec<Entity>,
+    },
+}
+
+/// The reason that a player was not able to take an action. Each error
+/// corresponds to a rule that the action would have broken, which is available
+/// through [`ActionError::rule`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ActionError {
+    GameOver,
+    NoSuchPlayer { player: PlayerId },
+    NoSuchObject { object: Entity },
+    NotControlled { object: Entity, player: PlayerId },
+
+    NotPriorityPlayer,
+    NotActivePlayer,
+    NotMainPhase,
+    StackNotEmpty,
+
+    NotChoosingStartingPlayer,
+
+    NotDecidingMulligan,
+    NoMoreMulligans,
+    WrongBottomCount { required: usize, chosen: usize },
+    BottomChosenTwice { card: Entity },
+    BottomNotInHand { card: Entity },
+    MulliganAbilityNotInHand { card: Entity },
+    NoMulliganAbility { card: Entity },
+
+    NotALand { card: Entity },
+    LandNotInHand { card: Entity },
+    LandAlreadyPlayed,
+
+    SpellNotInHand { spell: Entity },
+    NotCastingSpell { spell: Entity },
+    NoManaCost { spell: Entity },
+    NoSuchMana { mana: ManaId },
+    ManaAlreadySpent { mana: ManaId },
+    NoManaDue,
+    ManaCannotPay { mana: ManaId },
+    ManaRestricted { mana: ManaId, spell: Entity },
+    CostNotPaid,
+    CannotPayCost,
+    XNotChosen { spell: Entity },
+    NoXInCost { spell: Entity },
+    XTooLarge { spell: Entity, x: u32 },
+    NoLifeDue,
+    NotEnoughLife { required: u32, life: i64 },
+
+    NotDeclaringAttackers,
+    AttackerNotCreature { attacker: Entity },
+    AttackerTapped { attacker: Entity },
+    AttackerChosenTwice { attacker: Entity },
+    InvalidAttackTarget { attacker: Entity, target: Target },
+
+    NotDeclaringBlockers,
+    BlockerNotCreature { blocker: Entity },
+    BlockerTapped { blocker: Entity },
+    BlockerChosenTwice { blocker: Entity },
+    CannotBlockAttacker { blocker: Entity, attacker: Entity },
+
+    NotDiscardingToHandSize,
+    WrongDiscardCount { required: usize, chosen: usize },
+    DiscardChosenTwice { card: Entity },
+    DiscardNotInHand { card: Entity },
+
+    NotChoosingReplacementEffect,
+    ReplacementEffectNotAnOption { effect: Entity },
 }
+
+impl ActionError {
+    /// The number of the comprehensive rule that forbids the action.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Self::GameOver => "104.1",
+            Self::NoSuchPlayer { .. } => "102.1",
+            Self::NoSuchObject { .. } => "109.1",
+            Self::NotControlled { .. } => "109.4",
+
+            Self::NotPriorityPlayer => "117.1",
+            Self::NotActivePlayer => "116.2a",
+            Self::NotMainPhase => "117.1a",
+            Self::StackNotEmpty => "117.1a",
+
+            Self::NotChoosingStartingPlayer => "103.1",
+
+            Self::NotDecidingMulligan => "103.4",
+            Self::NoMoreMulligans => "103.4",
+            Self::WrongBottomCount { .. } => "103.4",
+            Self::BottomChosenTwice { .. } => "103.4",
+            Self::BottomNotInHand { .. } => "103.4",
+            Self::MulliganAbilityNotInHand { .. } => "103.4b",
+            Self::NoMulliganAbility { .. } => "103.4b",
+
+            Self::NotALand { .. } => "305.1",
+            Self::LandNotInHand { .. } => "305.1",
+            Self::LandAlreadyPlayed => "305.2",
+
+            Self::SpellNotInHand { .. } => "601.3",
+            Self::NotCastingSpell { .. } => "601.2",
+            Self::NoManaCost { .. } => "601.2f",
+            Self::NoSuchMana { .. } => "601.2g",
+            Self::ManaAlreadySpent { .. } => "601.2h",
+            Self::NoManaDue => "601.2h",
+            Self::ManaCannotPay { .. } => "601.2h",
+            Self::ManaRestricted { .. } => "106.6",
+            Self::CostNotPaid => "601.2h",
+            Self::CannotPayCost => "601.2h",
+            Self::XNotChosen { .. } => "601.2b",
+            Self::NoXInCost { .. } => "601.2b",
+            Self::XTooLarge { .. } => "601.2b",
+            Self::NoLifeDue => "601.2h",
+            Self::NotEnoughLife { .. } => "119.4",
+
+            Self::NotDeclaringAttackers => "508.1",
+            Self::AttackerNotCreature { .. } => "508.1a",
+            Self::AttackerTapped { .. } => "508.1a",
+            Self::AttackerChosenTwice { .. } => "508.1a",
+            Self::InvalidAttackTarget { .. } => "508.1b",
+
+            Self::NotDeclaringBlockers => "509.1",
+            Self::BlockerNotCreature { .. } => "509.1a",
+            Self::BlockerTapped { .. } => "509.1a",
+            Self::BlockerChosenTwice { .. } => "509.1a",
+            Self::CannotBlockAttacker { .. } => "509.1a",
+
+            Self::NotDiscardingToHandSize => "514.1",
+            Self::WrongDiscardCount { .. } => "514.1",
+            Self::DiscardChosenTwice { .. } => "514.1",
+            Self::DiscardNotInHand { .. } => "701.8a",
+
+            Self::NotChoosingReplacementEffect => "616.1",
+            Self::ReplacementEffectNotAnOption { .. } => "616.1e",
+        }
+    }
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: ", self.rule())?;
+
+        match self {
+            Self::GameOver => write!(formatter, "the game has ended"),
+            Self::NoSuchPlayer { player } => write!(formatter, "{:?} is not a player", player),
+            Self::NoSuchObject { object } => write!(formatter, "{:?} is not an object", object),
+            Self::NotControlled { object, player } => {
+                write!(formatter, "{:?} is not controlled by {:?}", object, player)
+            }
+
+            Self::NotPriorityPlayer => write!(formatter, "player does not have priority"),
+            Self::NotActivePlayer => write!(formatter, "it is not the player's turn"),
+            Self::NotMainPhase => write!(formatter, "it is not a main phase"),
+            Self::StackNotEmpty => write!(formatter, "stack is not empty"),
+
+            Self::NotChoosingStartingPlayer => {
+                write!(formatter, "player is not choosing who plays first")
+            }
+
+            Self::NotDecidingMulligan => {
+                write!(formatter, "player is not deciding whether to mulligan")
+            }
+            Self::NoMoreMulligans => write!(
+                formatter,
+                "player has no cards left to take another mulligan with"
+            ),
+            Self::WrongBottomCount { required, chosen } => write!(
+                formatter,
+                "{} cards must be put on the bottom of the library, but {} were chosen",
+                required, chosen
+            ),
+            Self::BottomChosenTwice { card } => {
+                write!(formatter, "card {:?} was chosen more than once", card)
+            }
+            Self::BottomNotInHand { card } => {
+                write!(formatter, "card {:?} is not in the player's hand", card)
+            }
+            Self::MulliganAbilityNotInHand { card } => {
+                write!(formatter, "card {:?} is not in the player's hand", card)
+            }
+            Self::NoMulliganAbility { card } => write!(
+                formatter,
+                "card {:?} has no ability that can be used at mulligan time",
+                card
+            ),
+
+            Self::NotALand { card } => write!(formatter, "{:?} is not a land", card),
+            Self::LandNotInHand { card } => {
+                write!(formatter, "land {:?} is not in the player's hand", card)
+            }
+            Self::LandAlreadyPlayed => {
+                write!(formatter, "player has already played a land this turn")
+            }
+
+            Self::SpellNotInHand { spell } => {
+                write!(formatter, "spell {:?} is not in the player's hand", spell)
+            }
+            Self::NotCastingSpell { spell } => {
+                write!(formatter, "{:?} is not a spell being cast", spell)
+            }
+            Self::NoManaCost { spell } => write!(formatter, "spell {:?} has no mana cost", spell),
+            Self::NoSuchMana { mana } => {
+                write!(
+                    formatter,
+                    "mana {:?} is not in the player's mana pool",
+                    mana
+                )
+            }
+            Self::ManaAlreadySpent { mana } => {
+                write!(formatter, "mana {:?} was already spent", mana)
+            }
+            Self::NoManaDue => write!(formatter, "no more mana needs to be paid"),
+            Self::ManaCannotPay { mana } => {
+                write!(
+                    formatter,
+                    "mana {:?} cannot pay any unpaid part of the cost",
+                    mana
+                )
+            }
+            Self::ManaRestricted { mana, spell } => {
+                write!(
+                    formatter,
+                    "mana {:?} can't be spent on spell {:?}",
+                    mana, spell
+                )
+            }
+            Self::CostNotPaid => write!(formatter, "the total cost has not been paid"),
+            Self::CannotPayCost => write!(
+                formatter,
+                "the mana in the player's mana pool can't pay the rest of the cost"
+            ),
+            Self::XNotChosen { spell } => {
+                write!(
+                    formatter,
+                    "a value for X must be chosen to cast {:?}",
+                    spell
+                )
+            }
+            Self::NoXInCost { spell } => {
+                write!(formatter, "the mana cost of {:?} has no X", spell)
+            }
+            Self::XTooLarge { spell, x } => write!(
+                formatter,
+                "X can't be {} for {:?}, since costs can have at most {} generic mana",
+                x, spell, MAX_GENERIC_MANA
+            ),
+            Self::NoLifeDue => write!(formatter, "no Phyrexian mana is left to pay with life"),
+            Self::NotEnoughLife { required, life } => write!(
+                formatter,
+                "paying {} life requires at least that much life, but the player has {}",
+                required, life
+            ),
+
+            Self::NotDeclaringAttackers => write!(formatter, "player is not declaring attackers"),
+            Self::AttackerNotCreature { attacker } => {
+                write!(formatter, "attacker {:?} is not a creature", attacker)
+            }
+            Self::AttackerTapped { attacker } => {
+                write!(formatter, "attacker {:?} is tapped", attacker)
+            }
+
+            Self::AttackerChosenTwice { attacker } => {
+                write!(
+                    formatter,
+                    "attacker {:?} was chosen more than once",
+                    attacker
+                )
+            }
+            Self::InvalidAttackTarget { attacker, target } => write!(
+                formatter,
+                "attacker {:?} cannot attack {:?}",
+                attacker, target
+            ),
+
+            Self::NotDeclaringBlockers => write!(formatter, "player is not declaring blockers"),
+            Self::BlockerNotCreature { blocker } => {
+                write!(formatter, "blocker {:?} is not a creature", blocker)
+            }
+            Self::BlockerTapped { blocker } => {
+                write!(formatter, "blocker {:?} is tapped", blocker)
+            }
+            Self::BlockerChosenTwice { blocker } => {
+                write!(formatter, "blocker {:?} was chosen more than once", blocker)
+            }
+            Self::CannotBlockAttacker { blocker, attacker } => write!(
+                formatter,
+                "{:?} cannot block {:?}, which is not attacking the player or their planeswalkers",
+                blocker, attacker
+            ),
+
+            Self::NotDiscardingToHandSize => {
+                write!(formatter, "player is not discarding to hand size")
+            }
+            Self::WrongDiscardCount { required, chosen } => write!(
+                formatter,
+                "{} cards must be discarded, but {} were chosen",
+                required, chosen
+            ),
+            Self::DiscardChosenTwice { card } => {
+                write!(formatter, "card {:?} was chosen more than once", card)
+            }
+            Self::DiscardNotInHand { card } => {
+                write!(formatter, "card {:?} is not in the player's hand", card)
+            }
+
+            Self::NotChoosingReplacementEffect => {
+                write!(formatter, "player is not choosing a replacement effect")
+            }
+            Self::ReplacementEffectNotAnOption { effect } => {
+                write!(formatter, "replacement effect {:?} does not apply", effect)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ActionError {}
@@ -2,6 +2,7 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::ability::{ChapterAbility, MulliganAbility};
 use crate::cost::ManaCost;
 use crate::ident::Ident;
 use crate::pt::PtCharacteristic;
@@ -26,4 +27,10 @@ pub struct CardDescriptor {
 
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub pt: Option<PtCharacteristic>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub chapter_abilities: Vec<ChapterAbility>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub mulligan_abilities: Vec<MulliganAbility>,
 }
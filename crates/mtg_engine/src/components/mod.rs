@@ -7,10 +7,11 @@ use serde::{Deserialize, Serialize};
 
 use crate::cost::ManaCost;
 use crate::counters::Counter;
-use crate::mana_pool::ManaId;
+use crate::effect::Effect;
+use crate::mana_pool::{ManaId, ManaPool};
 use crate::object_db::CardId;
+use crate::player::PlayerId;
 use crate::target::Target;
-use crate::zone::ZoneId;
 
 mod object;
 
@@ -18,12 +19,21 @@ pub use object::*;
 
 /// A component to indicate that this entity is an effect that should be cleaned
 /// up at the end of the turn.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct UntilEotEffect;
 
+/// 613.7. Within a layer or sublayer, determining which order effects are
+///        applied in is usually done using a timestamp system. An effect with
+///        an earlier timestamp is applied before an effect with a later
+///        timestamp.
+///
+/// Effects without a timestamp apply before any that have one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Timestamp(pub u64);
+
 /// Indicates that this entity's lifetime is tied to another entity. Used for
 /// clearing static effects attached to permanents.
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AttachedToEntity {
     pub target: Entity,
 }
@@ -45,21 +55,90 @@ pub struct Card {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IncompleteSpell {
-    pub previous_zone: ZoneId,
+    /// 601.2f The player determines the total cost of the spell. [...]
+    ///
+    /// Any {X} in the spell's mana cost has been replaced with the chosen
+    /// amount of generic mana.
     pub total_cost: ManaCost,
-    pub mana_paid: Vec<ManaId>,
+
+    /// 601.2b [...] If the spell has a variable cost that will be paid as it’s
+    ///        being cast (such as an {X} in its mana cost; see rule 107.3),
+    ///        the player announces the value of that variable. [...]
+    pub x: Option<u32>,
+
+    /// What has been paid toward each item of `total_cost`, in the same order.
+    pub payments: Vec<CostItemPayment>,
+
     pub targets: Vec<Target>,
 }
 
+/// The mana and life paid toward one item of a spell's total cost.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CostItemPayment {
+    pub mana: Vec<ManaId>,
+    pub life: u32,
+}
+
 impl IncompleteSpell {
-    pub fn new(previous_zone: ZoneId, total_cost: ManaCost) -> Self {
+    pub fn new(total_cost: ManaCost, x: Option<u32>) -> Self {
         Self {
-            previous_zone,
+            payments: vec![CostItemPayment::default(); total_cost.items.len()],
             total_cost,
-            mana_paid: Vec::new(),
+            x,
             targets: Vec::new(),
         }
     }
+
+    /// Every mana paid toward the spell so far.
+    pub fn mana_paid(&self) -> impl Iterator<Item = ManaId> + '_ {
+        self.payments
+            .iter()
+            .flat_map(|payment| payment.mana.iter().copied())
+    }
+
+    /// The total life paid toward the spell so far.
+    pub fn life_paid(&self) -> u32 {
+        self.payments.iter().map(|payment| payment.life).sum()
+    }
+
+    /// Whether every item of the total cost has been paid for, using mana
+    /// from the given pool.
+    pub fn is_paid(&self, mana_pool: &ManaPool) -> bool {
+        self.total_cost
+            .items
+            .iter()
+            .zip(&self.payments)
+            .all(|(item, payment)| {
+                let mana: Vec<_> = payment
+                    .mana
+                    .iter()
+                    .filter_map(|&id| mana_pool.get(id).cloned())
+                    .collect();
+
+                item.is_paid_by(&mana, payment.life)
+            })
+    }
+}
+
+/// 603.1. Triggered abilities have a trigger condition and an effect.
+///
+/// Attached to abilities that have triggered, both while they wait to be put on
+/// the stack and once they are on the stack as objects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TriggeredAbility {
+    /// The object whose ability triggered.
+    pub source: Entity,
+
+    /// 603.3a A triggered ability is controlled by the player who controlled
+    ///        its source when it triggered, unless it’s a delayed triggered
+    ///        ability.
+    pub controller: PlayerId,
+
+    pub effects: Vec<Effect>,
+
+    /// If this is a Saga's chapter ability, the chapter number that caused it
+    /// to trigger.
+    pub chapter: Option<u32>,
 }
 
 #[derive(Debug)]
@@ -72,12 +151,99 @@ pub struct Permanent {
     pub tapped: bool,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Counters {
     pub counters: Vec<Counter>,
 }
 
-#[derive(Debug)]
+impl Counters {
+    pub fn new() -> Self {
+        Self {
+            counters: Vec::new(),
+        }
+    }
+
+    /// Returns how many of the given counter are present.
+    pub fn count(&self, counter: &Counter) -> usize {
+        self.counters.iter().filter(|&c| c == counter).count()
+    }
+
+    pub fn add(&mut self, counter: Counter, amount: usize) {
+        for _ in 0..amount {
+            self.counters.push(counter.clone());
+        }
+    }
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// 120.6. Damage marked on a creature remains until the cleanup step, even if
+///        that permanent stops being a creature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Damage {
     pub amount: u64,
 }
+
+/// 506.4. A permanent that’s removed from combat stops being an attacking,
+///        blocking, blocked, and/or unblocked creature.
+///
+/// Attached to creatures that are attacking, until they are removed from
+/// combat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attacking {
+    /// The player or planeswalker this creature is attacking.
+    pub target: Target,
+
+    /// 509.1h An attacking creature with one or more creatures declared as
+    ///        blockers for it becomes a blocked creature; one with no creatures
+    ///        declared as blockers for it becomes an unblocked creature. This
+    ///        remains unchanged until the creature is removed from combat, an
+    ///        effect says that it becomes blocked or unblocked, or the combat
+    ///        phase ends, whichever comes first.
+    pub blocked: bool,
+
+    /// The creatures blocking this one, in the order that it assigns combat
+    /// damage to them.
+    pub blockers: Vec<Entity>,
+}
+
+/// Attached to creatures that are blocking, until they are removed from
+/// combat.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Blocking {
+    /// The attacking creature that this creature is blocking.
+    pub attacker: Entity,
+}
+
+/// 400.2. [...] Library and hand are hidden zones, even if all the cards in
+///        one such zone happen to be revealed.
+///
+/// Attached to cards in hidden zones that some players are allowed to see,
+/// until they change zones.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Revealed {
+    /// Every player and spectator can see the card.
+    ToAll,
+
+    /// Only these players can see the card.
+    ToPlayers { players: Vec<PlayerId> },
+}
+
+impl Revealed {
+    /// Whether the card can be seen by the given player, or by spectators if
+    /// `player` is `None`.
+    pub fn is_visible_to(&self, player: Option<PlayerId>) -> bool {
+        match self {
+            Self::ToAll => true,
+            Self::ToPlayers { players } => match player {
+                Some(player) => players.contains(&player),
+                None => false,
+            },
+        }
+    }
+}
@@ -1,5 +1,6 @@
 use serde::{Deserialize, Serialize};
 
+use crate::ability::{ChapterAbility, MulliganAbility};
 use crate::cost::ManaCost;
 use crate::ident::Ident;
 use crate::player::PlayerId;
@@ -25,6 +26,8 @@ pub struct Object {
     pub subtypes: Vec<CardSubtype>,
     pub pt: Option<PtCharacteristic>,
     pub mana_cost: Option<ManaCost>,
+    pub chapter_abilities: Vec<ChapterAbility>,
+    pub mulligan_abilities: Vec<MulliganAbility>,
 
     pub zone: ZoneId,
 
@@ -62,3 +65,36 @@ pub struct Object {
     ///        its owner. See rule 905.5.
     pub controller: Option<PlayerId>,
 }
+
+impl Object {
+    /// The player who controls this object if it enters the battlefield.
+    ///
+    /// 110.2. [...] A permanent’s controller is, by default, the player under
+    ///        whose control it entered the battlefield. [...]
+    ///
+    /// 608.3a If the object that’s resolving is a permanent spell, its
+    ///        resolution involves a single step (unless it’s an Aura, a copy
+    ///        of a permanent spell, or a mutating creature spell). The
+    ///        permanent card becomes a permanent and is put onto the
+    ///        battlefield under the control of the spell’s controller.
+    pub fn controller_on_battlefield(&self) -> PlayerId {
+        self.controller.unwrap_or(self.owner)
+    }
+
+    pub fn has_subtype(&self, name: &str) -> bool {
+        self.subtypes
+            .iter()
+            .any(|subtype| subtype.name.as_ref() == name)
+    }
+
+    /// 714.2c A Saga’s final chapter number is the greatest value among
+    ///        chapter abilities it has. If a Saga somehow has no chapter
+    ///        abilities, its final chapter number is 0.
+    pub fn final_chapter_number(&self) -> u32 {
+        self.chapter_abilities
+            .iter()
+            .flat_map(|ability| ability.chapters.iter().copied())
+            .max()
+            .unwrap_or(0)
+    }
+}
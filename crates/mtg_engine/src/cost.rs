@@ -5,33 +5,328 @@
 //!
 //! There are many kinds of cost for various spells and abilities in the game.
 
-use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Deserializer, Serialize};
 
 use crate::mana_pool::Mana;
 
 /// 202. Mana Cost and Color
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// In card data, a mana cost can be written either as a list of items or in
+/// the usual notation with mana symbols, like `"{2}{G}{G}"`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
 #[serde(transparent)]
 pub struct ManaCost {
     pub items: Vec<ManaCostItem>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum ManaCostItem {
-    ColoredMana { color: ManaColor },
+    ColoredMana {
+        color: ManaColor,
+    },
     GenericMana,
+
+    /// 107.3. Many objects use the letter X as a placeholder for a number that
+    ///        needs to be determined. [...]
+    XGenericMana,
+
+    /// 107.4e Hybrid mana symbols are also colored mana symbols. Each one
+    ///        represents a cost that can be paid in one of two ways, as
+    ///        represented by the two halves of the symbol. A hybrid symbol
+    ///        such as {W/U} can be paid with either white or blue mana, and a
+    ///        monocolored hybrid symbol such as {2/B} can be paid with either
+    ///        one black mana or two mana of any type. [...]
+    HybridMana {
+        first: ManaColor,
+        second: ManaColor,
+    },
+    MonocoloredHybridMana {
+        color: ManaColor,
+    },
+
+    /// 107.4c The colorless mana symbol {C} is used to represent one colorless
+    ///        mana, and also to represent a cost that can be paid only with one
+    ///        colorless mana.
+    ColorlessMana,
+
+    /// 107.4f Phyrexian mana symbols are colored mana symbols: {W/P} is white,
+    ///        {U/P} is blue, {B/P} is black, {R/P} is red, and {G/P} is green.
+    ///        A Phyrexian mana symbol represents a cost that can be paid either
+    ///        with one mana of its color or by paying 2 life.
+    PhyrexianMana {
+        color: ManaColor,
+    },
+
+    /// 107.4h The snow mana symbol {S} represents one mana in a cost. This mana
+    ///        can be paid with one mana of any type produced by a snow
+    ///        permanent (see rule 205.4g). [...]
+    SnowMana,
 }
 
+/// The life paid instead of mana for a Phyrexian mana symbol.
+pub const PHYREXIAN_LIFE: u32 = 2;
+
 impl ManaCostItem {
+    /// Whether a single mana can pay for this item on its own.
     pub fn can_be_paid_with(&self, mana: &Mana) -> bool {
         match self {
-            ManaCostItem::ColoredMana { color } => mana.color == *color,
+            ManaCostItem::ColoredMana { color } => mana.color == Some(*color),
             ManaCostItem::GenericMana => true,
+
+            // X is replaced with generic mana once it's chosen, so it's never
+            // paid itself.
+            ManaCostItem::XGenericMana => false,
+
+            ManaCostItem::HybridMana { first, second } => {
+                mana.color == Some(*first) || mana.color == Some(*second)
+            }
+            ManaCostItem::MonocoloredHybridMana { color } => mana.color == Some(*color),
+            ManaCostItem::ColorlessMana => mana.color.is_none(),
+            ManaCostItem::PhyrexianMana { color } => mana.color == Some(*color),
+            ManaCostItem::SnowMana => mana.snow,
+        }
+    }
+
+    /// Whether the given mana and life pay for this item in full.
+    pub fn is_paid_by(&self, mana: &[Mana], life: u32) -> bool {
+        match self {
+            ManaCostItem::PhyrexianMana { .. } if life >= PHYREXIAN_LIFE => true,
+
+            // 107.4e [...] a monocolored hybrid symbol such as {2/B} can be
+            //        paid with either one black mana or two mana of any type.
+            ManaCostItem::MonocoloredHybridMana { .. } if mana.len() >= 2 => true,
+
+            _ => mana.iter().any(|mana| self.can_be_paid_with(mana)),
+        }
+    }
+
+    /// Whether another mana can go toward paying this item, after the given
+    /// mana and life have already been paid toward it.
+    pub fn accepts(&self, paid: &[Mana], life: u32, mana: &Mana) -> bool {
+        if self.is_paid_by(paid, life) {
+            return false;
+        }
+
+        match self {
+            ManaCostItem::MonocoloredHybridMana { .. } => true,
+            _ => self.can_be_paid_with(mana),
+        }
+    }
+}
+
+impl ManaCost {
+    /// 202.3. The mana value of an object is a number equal to the total
+    ///        amount of mana in its mana cost, regardless of color.
+    ///
+    /// 202.3e When calculating the mana value of an object with an {X} in its
+    ///        mana cost, X is treated as 0 while the object is not on the
+    ///        stack [...]
+    ///
+    /// 202.3f When calculating the mana value of an object with a hybrid mana
+    ///        symbol in its mana cost, use the largest component of each
+    ///        hybrid symbol.
+    pub fn mana_value(&self) -> u32 {
+        self.items
+            .iter()
+            .map(|item| match item {
+                ManaCostItem::ColoredMana { .. } => 1,
+                ManaCostItem::GenericMana => 1,
+                ManaCostItem::XGenericMana => 0,
+                ManaCostItem::HybridMana { .. } => 1,
+                ManaCostItem::MonocoloredHybridMana { .. } => 2,
+                ManaCostItem::ColorlessMana => 1,
+                ManaCostItem::PhyrexianMana { .. } => 1,
+                ManaCostItem::SnowMana => 1,
+            })
+            .sum()
+    }
+
+    /// 202.2. An object is the color or colors of the mana symbols in its mana
+    ///        cost, regardless of the color of its frame.
+    ///
+    /// The colors are in the usual order: white, blue, black, red and green.
+    pub fn colors(&self) -> Vec<ManaColor> {
+        ManaColor::ALL
+            .into_iter()
+            .filter(|&color| {
+                self.items.iter().any(|item| match *item {
+                    ManaCostItem::ColoredMana { color: other } => other == color,
+                    ManaCostItem::HybridMana { first, second } => first == color || second == color,
+                    ManaCostItem::MonocoloredHybridMana { color: other } => other == color,
+                    ManaCostItem::PhyrexianMana { color: other } => other == color,
+                    ManaCostItem::GenericMana
+                    | ManaCostItem::XGenericMana
+                    | ManaCostItem::ColorlessMana
+                    | ManaCostItem::SnowMana => false,
+                })
+            })
+            .collect()
+    }
+}
+
+/// A mana cost written with mana symbols that couldn't be understood.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseManaCostError {
+    /// The text isn't made of mana symbols in braces.
+    NotSymbols { text: String },
+
+    /// The text inside a pair of braces isn't a known mana symbol.
+    UnknownSymbol { symbol: String },
+
+    /// The cost has more generic mana than [`MAX_GENERIC_MANA`].
+    TooMuchGenericMana { symbol: String },
+}
+
+/// The most generic mana a cost can have, which is the cost of Gleemax. Costs
+/// are stored one mana at a time, so larger ones are rejected rather than
+/// filling memory.
+pub const MAX_GENERIC_MANA: usize = 1_000_000;
+
+impl FromStr for ManaCost {
+    type Err = ParseManaCostError;
+
+    /// Parse a mana cost written with mana symbols, like `{1}{G}`, `{X}{R}{R}`,
+    /// `{W/U}`, `{2/B}`, `{G/P}`, `{C}` or `{S}`. A cost of `{0}` has no
+    /// items.
+    fn from_str(source: &str) -> Result<Self, Self::Err> {
+        let mut items = Vec::new();
+        let mut generic_total = 0;
+        let mut rest = source.trim();
+
+        while !rest.is_empty() {
+            let (symbol, after) = rest
+                .strip_prefix('{')
+                .and_then(|inner| inner.split_once('}'))
+                .ok_or_else(|| ParseManaCostError::NotSymbols {
+                    text: source.to_owned(),
+                })?;
+            rest = after;
+
+            let unknown = || ParseManaCostError::UnknownSymbol {
+                symbol: symbol.to_owned(),
+            };
+
+            if !symbol.is_empty() && symbol.bytes().all(|byte| byte.is_ascii_digit()) {
+                let generic = symbol
+                    .parse::<usize>()
+                    .ok()
+                    .filter(|&generic| generic <= MAX_GENERIC_MANA - generic_total)
+                    .ok_or_else(|| ParseManaCostError::TooMuchGenericMana {
+                        symbol: symbol.to_owned(),
+                    })?;
+                generic_total += generic;
+                items.extend(std::iter::repeat_n(ManaCostItem::GenericMana, generic));
+                continue;
+            }
+
+            let item = match symbol.split_once('/') {
+                None if symbol == "X" => ManaCostItem::XGenericMana,
+                None if symbol == "C" => ManaCostItem::ColorlessMana,
+                None if symbol == "S" => ManaCostItem::SnowMana,
+                None => ManaCostItem::ColoredMana {
+                    color: ManaColor::from_symbol(symbol).ok_or_else(unknown)?,
+                },
+                Some(("2", color)) => ManaCostItem::MonocoloredHybridMana {
+                    color: ManaColor::from_symbol(color).ok_or_else(unknown)?,
+                },
+                Some((color, "P")) => ManaCostItem::PhyrexianMana {
+                    color: ManaColor::from_symbol(color).ok_or_else(unknown)?,
+                },
+                Some((first, second)) => ManaCostItem::HybridMana {
+                    first: ManaColor::from_symbol(first).ok_or_else(unknown)?,
+                    second: ManaColor::from_symbol(second).ok_or_else(unknown)?,
+                },
+            };
+
+            items.push(item);
+        }
+
+        Ok(ManaCost { items })
+    }
+}
+
+impl fmt::Display for ManaCost {
+    /// Write the cost with mana symbols, combining generic mana into a single
+    /// number like `{2}{G}{G}`.
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        if self.items.is_empty() {
+            return write!(formatter, "{{0}}");
+        }
+
+        let mut generic = 0;
+
+        for (index, item) in self.items.iter().enumerate() {
+            match item {
+                ManaCostItem::ColoredMana { color } => write!(formatter, "{{{}}}", color.symbol())?,
+                ManaCostItem::GenericMana => {
+                    generic += 1;
+
+                    if self.items.get(index + 1) != Some(&ManaCostItem::GenericMana) {
+                        write!(formatter, "{{{}}}", generic)?;
+                        generic = 0;
+                    }
+                }
+                ManaCostItem::XGenericMana => write!(formatter, "{{X}}")?,
+                ManaCostItem::HybridMana { first, second } => {
+                    write!(formatter, "{{{}/{}}}", first.symbol(), second.symbol())?
+                }
+                ManaCostItem::MonocoloredHybridMana { color } => {
+                    write!(formatter, "{{2/{}}}", color.symbol())?
+                }
+                ManaCostItem::ColorlessMana => write!(formatter, "{{C}}")?,
+                ManaCostItem::PhyrexianMana { color } => {
+                    write!(formatter, "{{{}/P}}", color.symbol())?
+                }
+                ManaCostItem::SnowMana => write!(formatter, "{{S}}")?,
+            }
         }
+
+        Ok(())
     }
 }
 
+impl<'de> Deserialize<'de> for ManaCost {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Notation(String),
+            Items(Vec<ManaCostItem>),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Notation(source) => source.parse().map_err(serde::de::Error::custom),
+            Repr::Items(items) => Ok(ManaCost { items }),
+        }
+    }
+}
+
+impl fmt::Display for ParseManaCostError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotSymbols { text } => write!(
+                formatter,
+                "{:?} is not a list of mana symbols like {{1}}{{G}}",
+                text
+            ),
+            Self::UnknownSymbol { symbol } => {
+                write!(formatter, "{{{}}} is not a known mana symbol", symbol)
+            }
+            Self::TooMuchGenericMana { symbol } => write!(
+                formatter,
+                "{{{}}} makes the cost more than {} generic mana",
+                symbol, MAX_GENERIC_MANA
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ParseManaCostError {}
+
 /// An action or payment necessary to take another action or to stop another
 /// action from taking place. See rule 118, "Costs."
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -88,7 +383,7 @@ pub enum CostItem {
     //        color nor a type of mana.
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ManaColor {
     White,
     Blue,
@@ -96,3 +391,29 @@ pub enum ManaColor {
     Red,
     Green,
 }
+
+impl ManaColor {
+    /// Every color, in the usual order.
+    pub const ALL: [ManaColor; 5] = [
+        ManaColor::White,
+        ManaColor::Blue,
+        ManaColor::Black,
+        ManaColor::Red,
+        ManaColor::Green,
+    ];
+
+    /// The letter used for this color in mana symbols, like the G in {G}.
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            ManaColor::White => "W",
+            ManaColor::Blue => "U",
+            ManaColor::Black => "B",
+            ManaColor::Red => "R",
+            ManaColor::Green => "G",
+        }
+    }
+
+    pub fn from_symbol(symbol: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|color| color.symbol() == symbol)
+    }
+}
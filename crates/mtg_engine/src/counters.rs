@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 use crate::ident::Ident;
 use crate::keyword_ability::KeywordAbility;
 use crate::pt::PtValue;
@@ -9,7 +11,7 @@ use crate::pt::PtValue;
 ///        Counters are not objects and have no characteristics. Notably, a
 ///        counter is not a token, and a token is not a counter. Counters with
 ///        the same name or description are interchangeable.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Counter {
     /// 122.1a A +X/+Y counter on a creature or on a creature card in a zone
     ///        other than the battlefield, where X and Y are numbers, adds X to
@@ -39,6 +41,10 @@ pub enum Counter {
     ///        810 for additional rules for Two-Headed Giant games.)
     Poison(i64),
 
+    /// Lore counters track the progress of a Saga through its chapter
+    /// abilities. See rule 714, “Saga Cards.”
+    Lore,
+
     /// Other counters named by cards, like "filibuster counters" from Azor's
     /// Elocutors.
     Other(Ident),
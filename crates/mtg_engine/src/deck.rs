@@ -0,0 +1,275 @@
+//! 100.2. To play, each player needs their own deck of traditional Magic cards,
+//!        small items to represent any tokens and counters, and some way to
+//!        clearly track life totals.
+//!
+//! Decks are usually written as plain-text decklists, with one line for each
+//! card and how many copies of it the deck has:
+//!
+//! ```text
+//! 20 Forest
+//! 4 Grizzly Bears
+//!
+//! Sideboard
+//! 3 Giant Growth
+//! ```
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::object_db::{CardId, ObjectDb};
+
+/// A deck, along with the sideboard that may come with it. See rule 100.4.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Deck {
+    pub main: Vec<DeckEntry>,
+    pub sideboard: Vec<DeckEntry>,
+
+    /// 903.3. Each deck has a legendary card designated as its commander.
+    ///        [...]
+    ///
+    /// Empty for decks that aren't Commander decks.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub commander: Vec<DeckEntry>,
+}
+
+/// The most copies of a card one line of a decklist can have. Decks are
+/// expanded into one entry per copy, so larger counts are rejected rather than
+/// filling memory.
+pub const MAX_COPIES_PER_LINE: u32 = 10_000;
+
+/// One line of a decklist: a card and how many copies of it there are.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DeckEntry {
+    pub count: u32,
+    pub card: CardId,
+}
+
+/// Every line of a decklist that couldn't be understood.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseDeckError {
+    pub errors: Vec<DeckLineError>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeckLineError {
+    /// The line of the decklist the error is on, starting from 1.
+    pub line: usize,
+    pub kind: DeckLineErrorKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DeckLineErrorKind {
+    /// The line doesn't start with a number of copies.
+    MissingCount { text: String },
+
+    /// The line has a number of copies, but no card name after it.
+    MissingName { text: String },
+
+    /// The number of copies is 0 or more than [`MAX_COPIES_PER_LINE`].
+    InvalidCount { text: String },
+
+    /// No card with this name is in the object database.
+    UnknownCard { name: String },
+}
+
+impl Deck {
+    /// Parse a plain-text decklist, resolving each card's name against the
+    /// object database.
+    ///
+    /// Each line is a number of copies from 1 to [`MAX_COPIES_PER_LINE`]
+    /// followed by a card name, like
+    /// `4 Grizzly Bears` or `4x Grizzly Bears`. A set code and collector
+    /// number after the name, like `4 Grizzly Bears (M10) 175`, is ignored. A
+    /// line reading `Sideboard` starts the sideboard, a line reading
+    /// `Commander` starts the commander section, and a line reading `Deck`
+    /// goes back to the main deck. Lines starting with `SB:` are also part of
+    /// the sideboard. Blank lines and lines starting with `//` or `#` are
+    /// ignored.
+    ///
+    /// Every line that can't be parsed or names an unknown card is reported,
+    /// not just the first.
+    pub fn parse(source: &str, object_db: &ObjectDb) -> Result<Self, ParseDeckError> {
+        let mut deck = Deck::default();
+        let mut errors = Vec::new();
+        let mut section = Section::Main;
+
+        for (index, line) in source.lines().enumerate() {
+            let mut text = line.trim();
+
+            if text.is_empty() || text.starts_with("//") || text.starts_with('#') {
+                continue;
+            }
+
+            let header = text.trim_end_matches(':');
+            if header.eq_ignore_ascii_case("sideboard") {
+                section = Section::Sideboard;
+                continue;
+            }
+
+            if header.eq_ignore_ascii_case("commander") {
+                section = Section::Commander;
+                continue;
+            }
+
+            if header.eq_ignore_ascii_case("deck") || header.eq_ignore_ascii_case("maindeck") {
+                section = Section::Main;
+                continue;
+            }
+
+            let mut line_section = section;
+            if let Some(rest) = text.strip_prefix("SB:") {
+                text = rest.trim_start();
+                line_section = Section::Sideboard;
+            }
+
+            let (count, name) = match parse_line(text) {
+                Ok(parsed) => parsed,
+                Err(kind) => {
+                    errors.push(DeckLineError {
+                        line: index + 1,
+                        kind,
+                    });
+                    continue;
+                }
+            };
+
+            let card = match object_db.card_id(name) {
+                Some(card) => card,
+                None => {
+                    errors.push(DeckLineError {
+                        line: index + 1,
+                        kind: DeckLineErrorKind::UnknownCard {
+                            name: name.to_owned(),
+                        },
+                    });
+                    continue;
+                }
+            };
+
+            let entry = DeckEntry { count, card };
+            match line_section {
+                Section::Main => deck.main.push(entry),
+                Section::Sideboard => deck.sideboard.push(entry),
+                Section::Commander => deck.commander.push(entry),
+            }
+        }
+
+        if errors.is_empty() {
+            Ok(deck)
+        } else {
+            Err(ParseDeckError { errors })
+        }
+    }
+
+    /// Every card in the main deck, with one entry for each copy.
+    pub fn main_cards(&self) -> Vec<CardId> {
+        expand(&self.main)
+    }
+
+    /// Every card in the sideboard, with one entry for each copy.
+    pub fn sideboard_cards(&self) -> Vec<CardId> {
+        expand(&self.sideboard)
+    }
+
+    /// The number of cards in the main deck.
+    pub fn main_size(&self) -> u64 {
+        self.main.iter().map(|entry| u64::from(entry.count)).sum()
+    }
+
+    /// The number of cards in the sideboard.
+    pub fn sideboard_size(&self) -> u64 {
+        self.sideboard
+            .iter()
+            .map(|entry| u64::from(entry.count))
+            .sum()
+    }
+}
+
+/// The part of a decklist that lines are being read into.
+#[derive(Clone, Copy)]
+enum Section {
+    Main,
+    Sideboard,
+    Commander,
+}
+
+/// Split a line like `4x Grizzly Bears (M10) 175` into its count and name.
+fn parse_line(text: &str) -> Result<(u32, &str), DeckLineErrorKind> {
+    let (count, rest) = text.split_once(char::is_whitespace).unwrap_or((text, ""));
+    let count = count.strip_suffix(['x', 'X']).unwrap_or(count);
+
+    if count.is_empty() || !count.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(DeckLineErrorKind::MissingCount {
+            text: text.to_owned(),
+        });
+    }
+
+    let count = count
+        .parse()
+        .ok()
+        .filter(|count| (1..=MAX_COPIES_PER_LINE).contains(count))
+        .ok_or_else(|| DeckLineErrorKind::InvalidCount {
+            text: text.to_owned(),
+        })?;
+
+    let name = match rest.find(" (") {
+        Some(set_code) => &rest[..set_code],
+        None => rest,
+    };
+    let name = name.trim();
+
+    if name.is_empty() {
+        return Err(DeckLineErrorKind::MissingName {
+            text: text.to_owned(),
+        });
+    }
+
+    Ok((count, name))
+}
+
+fn expand(entries: &[DeckEntry]) -> Vec<CardId> {
+    entries
+        .iter()
+        .flat_map(|entry| std::iter::repeat_n(entry.card, entry.count as usize))
+        .collect()
+}
+
+impl fmt::Display for ParseDeckError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "invalid decklist:")?;
+
+        for error in &self.errors {
+            write!(formatter, "\n  {}", error)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl fmt::Display for DeckLineError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "line {}: ", self.line)?;
+
+        match &self.kind {
+            DeckLineErrorKind::MissingCount { text } => write!(
+                formatter,
+                "expected a number of copies before the card name in {:?}",
+                text
+            ),
+            DeckLineErrorKind::MissingName { text } => {
+                write!(formatter, "expected a card name in {:?}", text)
+            }
+            DeckLineErrorKind::InvalidCount { text } => write!(
+                formatter,
+                "the number of copies in {:?} must be from 1 to {}",
+                text, MAX_COPIES_PER_LINE
+            ),
+            DeckLineErrorKind::UnknownCard { name } => {
+                write!(formatter, "no card named {:?} exists", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for ParseDeckError {}
@@ -0,0 +1,41 @@
+//! 609. Effects
+//!
+//! 609.1. An effect is something that happens in the game as a result of a
+//!        spell or ability. When a spell, activated ability, or triggered
+//!        ability resolves, it may create one or more one-shot or continuous
+//!        effects.
+
+use serde::{Deserialize, Serialize};
+
+/// A one-shot effect that a spell or ability performs as it resolves. Effects
+/// are performed by the controller of the spell or ability.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Effect {
+    /// 121.1. A player draws a card by putting the top card of their library
+    ///        into their hand.
+    DrawCards { count: u32 },
+
+    /// The controller gains life. See rule 119, “Life.”
+    GainLife { amount: i64 },
+
+    /// 701.8a To discard a card, move it from its owner’s hand to that
+    ///        player’s graveyard.
+    DiscardHand,
+
+    /// The controller exiles all the cards from their hand, then draws that
+    /// many cards.
+    ExileHandAndDrawThatMany,
+
+    /// The controller discards cards from their hand at random.
+    DiscardAtRandom { count: u32 },
+
+    /// 705.1. [...] the affected player flips the coin and calls heads or
+    ///        tails. If the call matches the result, that player wins the
+    ///        flip. Otherwise, that player loses the flip.
+    FlipCoin { won: Vec<Effect>, lost: Vec<Effect> },
+
+    /// Prevent all combat damage that would be dealt this turn, like Fog. See
+    /// rule 615, “Prevention Effects.”
+    PreventAllCombatDamage,
+}
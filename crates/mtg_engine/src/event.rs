@@ -0,0 +1,64 @@
+//! Events describing what happened in a game, for UIs and other observers that
+//! need to know how the game changed instead of only what it looks like now.
+
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::game::Step;
+use crate::player::PlayerId;
+use crate::target::Target;
+use crate::zone::ZoneId;
+
+/// Something that happened in the game. Events are emitted in the order they
+/// happened and collected by the game until they're drained with
+/// [`Game::drain_events`](crate::game::Game::drain_events).
+///
+/// Actions that fail don't emit any events.
+///
+/// Events aren't redacted for any player, so they can refer to objects in
+/// hidden zones that only some players, or none of them, can see.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum GameEvent {
+    /// An object moved from one zone to another.
+    ZoneChange {
+        object: Entity,
+        from: ZoneId,
+        to: ZoneId,
+    },
+
+    /// 121.1. A player draws a card by putting the top card of their library
+    ///        into their hand.
+    ///
+    /// Follows the zone change that moved the card.
+    Draw { player: PlayerId, card: Entity },
+
+    /// A source dealt damage to a player or permanent, after any replacement
+    /// and prevention effects were applied.
+    DamageDealt {
+        source: Entity,
+        target: Target,
+        amount: u64,
+        combat: bool,
+    },
+
+    /// A player's life total changed by `change`, to `life`.
+    LifeChanged {
+        player: PlayerId,
+        change: i64,
+        life: i64,
+    },
+
+    /// 601.2i [...] then the spell becomes cast.
+    SpellCast { player: PlayerId, spell: Entity },
+
+    /// A step or phase began. Phases without steps are reported as steps.
+    StepEntered {
+        step: Step,
+        active_player: PlayerId,
+        turn_number: u64,
+    },
+
+    /// 104.3. There are several ways to lose the game.
+    PlayerLost { player: PlayerId },
+}
@@ -0,0 +1,341 @@
+//! 100.2. To play, each player needs their own deck of traditional Magic cards
+//!        [...]
+//!
+//! A format decides which decks can be played: how large they are, how many
+//! copies of each card they can have, and which cards are banned.
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::deck::{Deck, DeckEntry};
+use crate::ident::Ident;
+use crate::object_db::{CardId, ObjectDb};
+use crate::types::{CardSupertype, CardType};
+
+/// The deck construction rules of a way of playing Magic.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Format {
+    pub name: String,
+
+    /// The fewest cards a deck can have, including its commander.
+    pub min_deck_size: u32,
+
+    /// The most cards a deck can have, including its commander, if there is a
+    /// limit.
+    pub max_deck_size: Option<u32>,
+
+    /// How many copies of a card with the same English name the deck and
+    /// sideboard can have combined, if there is a limit. Basic lands can
+    /// always have any number of copies.
+    pub max_copies: Option<u32>,
+
+    /// The most cards a sideboard can have, if there is a limit.
+    pub max_sideboard_size: Option<u32>,
+
+    /// Whether decks have a commander. See rule 903, “Commander.”
+    pub commander: bool,
+
+    /// 100.6. Most Magic tournaments (organized play activities where players
+    ///        compete against other players to win prizes) have additional
+    ///        rules covered in the Magic: The Gathering Tournament Rules [...].
+    ///        These rules may limit the use of some cards, including barring
+    ///        all cards from some older sets.
+    ///
+    /// Cards that decks in the format can't have, by name.
+    pub banned: Vec<Ident>,
+}
+
+/// A way that a deck breaks the rules of a format. Each violation corresponds
+/// to a rule, which is available through [`FormatViolation::rule`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum FormatViolation {
+    UnknownCard {
+        card: CardId,
+    },
+    /// `commander` is whether the format is a Commander format, which has
+    /// its own deck size rule.
+    TooFewCards {
+        minimum: u32,
+        actual: u64,
+        commander: bool,
+    },
+    TooManyCards {
+        maximum: u32,
+        actual: u64,
+        commander: bool,
+    },
+    TooManyCopies {
+        card: Ident,
+        maximum: u32,
+        actual: u64,
+    },
+    SideboardTooLarge {
+        maximum: u32,
+        actual: u64,
+    },
+    Banned {
+        card: Ident,
+    },
+    WrongCommanderCount {
+        actual: u64,
+    },
+    InvalidCommander {
+        card: Ident,
+    },
+}
+
+impl Format {
+    /// 100.2a In constructed play (a way of playing in which each player
+    ///        creates their own deck ahead of time), each deck has a minimum
+    ///        deck size of 60 cards. A constructed deck may contain any number
+    ///        of basic land cards and no more than four of any card with a
+    ///        particular English name other than basic land cards. [...]
+    ///
+    /// 100.4a In constructed play, a sideboard may contain no more than
+    ///        fifteen cards. [...]
+    pub fn constructed() -> Self {
+        Self {
+            name: "Constructed".to_owned(),
+            min_deck_size: 60,
+            max_deck_size: None,
+            max_copies: Some(4),
+            max_sideboard_size: Some(15),
+            commander: false,
+            banned: Vec::new(),
+        }
+    }
+
+    /// 100.2b In limited play (a way of playing in which each player gets the
+    ///        same quantity of unopened product such as booster packs and
+    ///        creates their own deck using only this product and basic land
+    ///        cards), each deck has a minimum deck size of 40 cards. [...]
+    pub fn limited() -> Self {
+        Self {
+            name: "Limited".to_owned(),
+            min_deck_size: 40,
+            max_deck_size: None,
+            max_copies: None,
+            max_sideboard_size: None,
+            commander: false,
+            banned: Vec::new(),
+        }
+    }
+
+    /// 903.5a Each deck must contain exactly 100 cards, including its
+    ///        commander. [...]
+    ///
+    /// 903.5b Other than basic lands, each card in a Commander deck must have
+    ///        a different English name. [...]
+    pub fn commander() -> Self {
+        Self {
+            name: "Commander".to_owned(),
+            min_deck_size: 100,
+            max_deck_size: Some(100),
+            max_copies: Some(1),
+            max_sideboard_size: Some(0),
+            commander: true,
+            banned: Vec::new(),
+        }
+    }
+
+    /// Ban the cards with the given names.
+    pub fn with_banned<'a>(mut self, names: impl IntoIterator<Item = &'a str>) -> Self {
+        self.banned.extend(names.into_iter().map(Ident::new));
+        self
+    }
+
+    /// Check a deck against the format, returning every rule it breaks. The
+    /// deck is legal in the format if there are none.
+    pub fn validate(&self, deck: &Deck, object_db: &ObjectDb) -> Vec<FormatViolation> {
+        let mut violations = Vec::new();
+
+        let deck_size = size(&deck.main) + size(&deck.commander);
+        if deck_size < u64::from(self.min_deck_size) {
+            violations.push(FormatViolation::TooFewCards {
+                minimum: self.min_deck_size,
+                actual: deck_size,
+                commander: self.commander,
+            });
+        }
+
+        if let Some(maximum) = self.max_deck_size {
+            if deck_size > u64::from(maximum) {
+                violations.push(FormatViolation::TooManyCards {
+                    maximum,
+                    actual: deck_size,
+                    commander: self.commander,
+                });
+            }
+        }
+
+        if let Some(maximum) = self.max_sideboard_size {
+            let actual = size(&deck.sideboard);
+            if actual > u64::from(maximum) {
+                violations.push(FormatViolation::SideboardTooLarge { maximum, actual });
+            }
+        }
+
+        // 100.4a [...] The four-card limit (see rule 100.2a) applies to the
+        //        combined deck and sideboard.
+        let mut copies: BTreeMap<Ident, (u64, bool)> = BTreeMap::new();
+        let entries = deck
+            .main
+            .iter()
+            .chain(&deck.sideboard)
+            .chain(&deck.commander);
+
+        for entry in entries {
+            let card = match object_db.card(entry.card) {
+                Some(card) => card,
+                None => {
+                    violations.push(FormatViolation::UnknownCard { card: entry.card });
+                    continue;
+                }
+            };
+
+            let basic = card.supertypes.contains(&CardSupertype::Basic);
+            copies.entry(card.name.clone()).or_insert((0, basic)).0 += u64::from(entry.count);
+        }
+
+        for (name, &(actual, basic)) in &copies {
+            if let Some(maximum) = self.max_copies {
+                if actual > u64::from(maximum) && !basic {
+                    violations.push(FormatViolation::TooManyCopies {
+                        card: name.clone(),
+                        maximum,
+                        actual,
+                    });
+                }
+            }
+
+            if self.banned.contains(name) {
+                violations.push(FormatViolation::Banned { card: name.clone() });
+            }
+        }
+
+        if self.commander {
+            self.validate_commander(deck, object_db, &mut violations);
+        }
+
+        violations
+    }
+
+    /// 903.3. Each deck has a legendary card designated as its commander. This
+    ///        card must be either (a) a creature card or (b) a planeswalker
+    ///        card with the ability to be your commander.
+    ///
+    /// TODO: Planeswalkers that can be your commander, partner commanders, and
+    /// the color identity rule in 903.5c.
+    fn validate_commander(
+        &self,
+        deck: &Deck,
+        object_db: &ObjectDb,
+        violations: &mut Vec<FormatViolation>,
+    ) {
+        let actual = size(&deck.commander);
+        if actual != 1 {
+            violations.push(FormatViolation::WrongCommanderCount { actual });
+        }
+
+        for entry in &deck.commander {
+            let card = match object_db.card(entry.card) {
+                Some(card) => card,
+                None => continue,
+            };
+
+            let legendary = card.supertypes.contains(&CardSupertype::Legendary);
+            let creature = card.types.contains(&CardType::Creature);
+
+            if !legendary || !creature {
+                violations.push(FormatViolation::InvalidCommander {
+                    card: card.name.clone(),
+                });
+            }
+        }
+    }
+}
+
+/// The number of cards in some entries of a deck. Counts are added as `u64`,
+/// so that no number of `u32` counts can overflow.
+fn size(entries: &[DeckEntry]) -> u64 {
+    entries.iter().map(|entry| u64::from(entry.count)).sum()
+}
+
+impl FormatViolation {
+    /// The number of the comprehensive rule that the deck breaks.
+    pub fn rule(&self) -> &'static str {
+        match self {
+            Self::UnknownCard { .. } => "100.2",
+            Self::TooFewCards {
+                commander: true, ..
+            }
+            | Self::TooManyCards {
+                commander: true, ..
+            } => "903.5a",
+            Self::TooFewCards { .. } => "100.2a",
+
+            // Only some formats limit how large a deck can be, so the limit
+            // is one of the format's own rules.
+            Self::TooManyCards { .. } => "100.6",
+            Self::TooManyCopies { .. } => "100.2a",
+            Self::SideboardTooLarge { .. } => "100.4a",
+            Self::Banned { .. } => "100.6",
+            Self::WrongCommanderCount { .. } => "903.3",
+            Self::InvalidCommander { .. } => "903.3",
+        }
+    }
+}
+
+impl fmt::Display for FormatViolation {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: ", self.rule())?;
+
+        match self {
+            Self::UnknownCard { card } => {
+                write!(formatter, "{:?} is not in the object database", card)
+            }
+            Self::TooFewCards {
+                minimum, actual, ..
+            } => write!(
+                formatter,
+                "the deck has {} cards, but needs at least {}",
+                actual, minimum
+            ),
+            Self::TooManyCards {
+                maximum, actual, ..
+            } => write!(
+                formatter,
+                "the deck has {} cards, but can have at most {}",
+                actual, maximum
+            ),
+            Self::TooManyCopies {
+                card,
+                maximum,
+                actual,
+            } => write!(
+                formatter,
+                "the deck and sideboard have {} copies of {}, but can have at most {}",
+                actual, card, maximum
+            ),
+            Self::SideboardTooLarge { maximum, actual } => write!(
+                formatter,
+                "the sideboard has {} cards, but can have at most {}",
+                actual, maximum
+            ),
+            Self::Banned { card } => write!(formatter, "{} is banned", card),
+            Self::WrongCommanderCount { actual } => write!(
+                formatter,
+                "the deck needs exactly one commander, but has {}",
+                actual
+            ),
+            Self::InvalidCommander { card } => write!(
+                formatter,
+                "{} can't be a commander because it isn't a legendary creature",
+                card
+            ),
+        }
+    }
+}
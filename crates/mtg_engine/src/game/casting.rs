@@ -4,10 +4,13 @@ use std::collections::HashSet;
 
 use hecs::Entity;
 
-use crate::action::PlayerActionCategory;
-use crate::components::{IncompleteSpell, Object};
+use crate::action::{ActionError, PlayerActionCategory};
+use crate::components::{CostItemPayment, IncompleteSpell, Object};
+use crate::cost::{ManaCost, ManaCostItem, MAX_GENERIC_MANA, PHYREXIAN_LIFE};
+use crate::event::GameEvent;
 use crate::game::GameState;
-use crate::mana_pool::ManaId;
+use crate::mana_payment::{self, AvailableMana};
+use crate::mana_pool::{ManaId, ManaPool};
 use crate::player::PlayerId;
 use crate::types::CardType;
 use crate::zone::ZoneId;
@@ -26,43 +29,48 @@ use super::{Game, Step};
 ///        the spell is illegal ; the game returns to the moment before the
 ///        casting of that spell was proposed (see rule 726, “Handling
 ///        Illegal Actions”).
-pub fn start_casting_spell(game: &mut Game, player: PlayerId, spell: Entity) {
+pub fn start_casting_spell(
+    game: &mut Game,
+    player: PlayerId,
+    spell: Entity,
+    x: Option<u32>,
+) -> Result<(), ActionError> {
+    let before = game.snapshot();
+
     let mut inner = || {
         // 601.3. A player can begin to cast a spell only if a rule or
         //        effect allows that player to cast it and no rule or effect
         //        prohibits that player from casting it.
         //
-        let previous_zone = {
+        {
             let spell_object = game
                 .world
                 .get::<Object>(spell)
-                .map_err(|_| "spell is not an Object")?;
+                .map_err(|_| ActionError::NoSuchObject { object: spell })?;
 
             // 117.1a A player may cast an instant spell any time they have
             //        priority. A player may cast a noninstant spell during
             //        their main phase any time they have priority and the stack
             //        is empty.
             if game.priority_player() != Some(player) {
-                return Err("player does not have priority");
+                return Err(ActionError::NotPriorityPlayer);
             }
 
             if !spell_object.types.contains(&CardType::Instant) {
                 if !game.zone(ZoneId::Stack).unwrap().is_empty() {
-                    return Err("stack is not empty");
+                    return Err(ActionError::StackNotEmpty);
                 }
 
                 if game.step != Step::Main1 && game.step != Step::Main2 {
-                    return Err("it is not a main phase");
+                    return Err(ActionError::NotMainPhase);
                 }
             }
 
             // By default, players can only cast spells in their hands.
             if spell_object.zone != ZoneId::Hand(player) {
-                return Err("spell is not in that player's hand");
+                return Err(ActionError::SpellNotInHand { spell });
             }
-
-            spell_object.zone
-        };
+        }
 
         // 601.2a To propose the casting of a spell, a player first moves that
         //        card (or that copy of a card) from where it is to the stack.
@@ -74,7 +82,30 @@ pub fn start_casting_spell(game: &mut Game, player: PlayerId, spell: Entity) {
         game.move_object_to_zone(spell, ZoneId::Stack);
 
         // 601.2b If the spell is modal, the player announces the mode
-        //        choice (see rule 700.2).
+        //        choice (see rule 700.2). [...] If the spell has a variable
+        //        cost that will be paid as it’s being cast (such as an {X} in
+        //        its mana cost; see rule 107.3), the player announces the
+        //        value of that variable.
+        {
+            let spell_object = game.world.get::<Object>(spell).unwrap();
+            let has_x = spell_object
+                .mana_cost
+                .as_ref()
+                .is_some_and(|cost| cost.items.contains(&ManaCostItem::XGenericMana));
+
+            match (has_x, x) {
+                (true, None) => return Err(ActionError::XNotChosen { spell }),
+                (false, Some(_)) => return Err(ActionError::NoXInCost { spell }),
+                _ => {}
+            }
+
+            // Mana can be added while the spell is being cast, so X isn't
+            // limited by the player's pool. Costs are stored one mana at a
+            // time, though, so X is bounded like printed generic mana.
+            if let Some(x) = x.filter(|&x| x as usize > MAX_GENERIC_MANA) {
+                return Err(ActionError::XTooLarge { spell, x });
+            }
+        }
 
         // 601.2c The player announces their choice of an appropriate object
         //        or player for each target the spell requires.
@@ -93,12 +124,27 @@ pub fn start_casting_spell(game: &mut Game, player: PlayerId, spell: Entity) {
         // 601.2f The player determines the total cost of the spell.
         let total_cost = {
             // Safe because we've checked that this was a legal object.
-            let spell = game.world.get::<Object>(spell).unwrap();
-
-            match &spell.mana_cost {
-                Some(mana_cost) => mana_cost.clone(),
-                None => return Err("spell has no mana cost"),
-            }
+            let spell_object = game.world.get::<Object>(spell).unwrap();
+
+            let mana_cost = match &spell_object.mana_cost {
+                Some(mana_cost) => mana_cost,
+                None => return Err(ActionError::NoManaCost { spell }),
+            };
+
+            // 107.3a [...] While a spell is on the stack, any X in its mana
+            //        cost [...] equals the announced value.
+            let items = mana_cost
+                .items
+                .iter()
+                .flat_map(|&item| match item {
+                    ManaCostItem::XGenericMana => {
+                        vec![ManaCostItem::GenericMana; x.unwrap_or(0) as usize]
+                    }
+                    item => vec![item],
+                })
+                .collect();
+
+            ManaCost { items }
         };
 
         // 601.2g If the total cost includes a mana payment, the player then
@@ -111,193 +157,438 @@ pub fn start_casting_spell(game: &mut Game, player: PlayerId, spell: Entity) {
         };
 
         game.world
-            .insert_one(spell, IncompleteSpell::new(previous_zone, total_cost))
+            .insert_one(spell, IncompleteSpell::new(total_cost, x))
             .unwrap();
 
         // ...continues in `pay_spell_mana` and `finish_casting_spell`
 
-        Ok::<(), &str>(())
+        Ok(())
     };
 
-    if let Err(err) = inner() {
-        log::error!("Player {:?} cannot cast spell {:?}: {}", player, spell, err);
-        cancel_casting_spell(game, player, spell);
+    match inner() {
+        Ok(()) => {
+            game.spell_proposals.push((spell, before));
+            Ok(())
+        }
+        Err(err) => {
+            game.restore(before);
+            Err(err)
+        }
     }
 }
 
-pub fn pay_spell_mana(game: &mut Game, player: PlayerId, spell: Entity, mana_id: ManaId) {
-    let inner = || {
-        let player_data = game.players.get(player).ok_or("player was invalid")?;
+pub fn pay_spell_mana(
+    game: &mut Game,
+    player: PlayerId,
+    spell: Entity,
+    mana_id: ManaId,
+) -> Result<(), ActionError> {
+    let player_data = game
+        .players
+        .get(player)
+        .ok_or(ActionError::NoSuchPlayer { player })?;
+
+    let spell_object = game
+        .world
+        .get::<Object>(spell)
+        .map_err(|_| ActionError::NoSuchObject { object: spell })?;
+
+    let mut incomplete = game
+        .world
+        .get_mut::<IncompleteSpell>(spell)
+        .map_err(|_| ActionError::NotCastingSpell { spell })?;
+
+    if spell_object.controller != Some(player) {
+        return Err(ActionError::NotControlled {
+            object: spell,
+            player,
+        });
+    }
+
+    let mana = player_data
+        .mana_pool
+        .get(mana_id)
+        .ok_or(ActionError::NoSuchMana { mana: mana_id })?;
+
+    if !mana.can_be_spent_on(&spell_object) {
+        return Err(ActionError::ManaRestricted {
+            mana: mana_id,
+            spell,
+        });
+    }
+
+    if incomplete.mana_paid().any(|paid| paid == mana_id) {
+        return Err(ActionError::ManaAlreadySpent { mana: mana_id });
+    }
+
+    if incomplete.is_paid(&player_data.mana_pool) {
+        return Err(ActionError::NoManaDue);
+    }
 
+    // Mana that's already been paid can move to other parts of the cost, so
+    // that paying generic mana first doesn't leave a colored symbol unpayable.
+    let mut mana_paid: Vec<_> = incomplete.mana_paid().collect();
+    mana_paid.push(mana_id);
+
+    incomplete.payments =
+        assign_spell_mana(&incomplete, &player_data.mana_pool, &mana_paid, &[], false)
+            .ok_or(ActionError::ManaCannotPay { mana: mana_id })?;
+
+    Ok(())
+}
+
+/// Pay the rest of a spell's cost with mana from the player's mana pool,
+/// keeping any mana and life the player has already paid.
+pub fn auto_pay_spell(game: &mut Game, player: PlayerId, spell: Entity) -> Result<(), ActionError> {
+    {
         let spell_object = game
             .world
             .get::<Object>(spell)
-            .map_err(|_| "spell is not an Object")?;
-
-        let mut incomplete = game
-            .world
-            .get_mut::<IncompleteSpell>(spell)
-            .map_err(|_| "spell is not an IncompleteSpell")?;
+            .map_err(|_| ActionError::NoSuchObject { object: spell })?;
 
         if spell_object.controller != Some(player) {
-            return Err("spell is not controlled by player");
+            return Err(ActionError::NotControlled {
+                object: spell,
+                player,
+            });
         }
+    }
 
-        let mana = player_data
-            .mana_pool
-            .get(mana_id)
-            .ok_or("mana ID was invalid")?;
+    let payments = auto_payment(game, player, spell)?;
 
-        if incomplete.mana_paid.contains(&mana_id) {
-            return Err("mana was already spent");
-        }
+    let mut incomplete = game
+        .world
+        .get_mut::<IncompleteSpell>(spell)
+        .map_err(|_| ActionError::NotCastingSpell { spell })?;
+    incomplete.payments = payments;
 
-        // TODO: Handle X mana
-        let amount_paid_so_far = incomplete.mana_paid.len();
-        let next_mana = incomplete
-            .total_cost
-            .items
-            .get(amount_paid_so_far)
-            .ok_or("no more mana needs to be paid")?;
-
-        if !next_mana.can_be_paid_with(&mana) {
-            return Err("mana cost cannot be paid with that mana");
-        }
+    Ok(())
+}
 
-        incomplete.mana_paid.push(mana_id);
+/// Find a payment for the rest of a spell's cost using the mana in the
+/// player's pool that isn't being spent on another spell.
+pub(super) fn auto_payment(
+    game: &Game,
+    player: PlayerId,
+    spell: Entity,
+) -> Result<Vec<CostItemPayment>, ActionError> {
+    let player_data = game
+        .players
+        .get(player)
+        .ok_or(ActionError::NoSuchPlayer { player })?;
+
+    let spell_object = game
+        .world
+        .get::<Object>(spell)
+        .map_err(|_| ActionError::NoSuchObject { object: spell })?;
+
+    let incomplete = game
+        .world
+        .get::<IncompleteSpell>(spell)
+        .map_err(|_| ActionError::NotCastingSpell { spell })?;
+
+    if incomplete.is_paid(&player_data.mana_pool) {
+        return Err(ActionError::NoManaDue);
+    }
 
-        Ok::<(), &str>(())
-    };
+    let mut query = game.world.query::<(&Object, &IncompleteSpell)>();
+    let committed: HashSet<_> = query
+        .iter()
+        .filter(|(_, (object, _))| object.controller == Some(player))
+        .flat_map(|(_, (_, other))| other.mana_paid().collect::<Vec<_>>())
+        .collect();
+
+    let mana_paid: Vec<_> = incomplete.mana_paid().collect();
+    let unspent: Vec<_> = player_data
+        .mana_pool
+        .iter()
+        .filter(|(id, mana)| !committed.contains(id) && mana.can_be_spent_on(&spell_object))
+        .map(|(id, _)| id)
+        .collect();
+
+    assign_spell_mana(
+        &incomplete,
+        &player_data.mana_pool,
+        &mana_paid,
+        &unspent,
+        true,
+    )
+    .ok_or(ActionError::CannotPayCost)
+}
 
-    if let Err(err) = inner() {
-        log::error!(
-            "Player {:?} cannot pay mana {:?} to cast spell {:?}: {}",
+/// 107.4f [...] A Phyrexian mana symbol represents a cost that can be paid
+///        either with one mana of its color or by paying 2 life.
+pub fn pay_spell_life(game: &mut Game, player: PlayerId, spell: Entity) -> Result<(), ActionError> {
+    let player_data = game
+        .players
+        .get(player)
+        .ok_or(ActionError::NoSuchPlayer { player })?;
+
+    let spell_object = game
+        .world
+        .get::<Object>(spell)
+        .map_err(|_| ActionError::NoSuchObject { object: spell })?;
+
+    let mut incomplete = game
+        .world
+        .get_mut::<IncompleteSpell>(spell)
+        .map_err(|_| ActionError::NotCastingSpell { spell })?;
+
+    if spell_object.controller != Some(player) {
+        return Err(ActionError::NotControlled {
+            object: spell,
             player,
-            mana_id,
-            spell,
-            err
-        );
+        });
     }
+
+    let payments =
+        life_payment(&incomplete, &player_data.mana_pool).ok_or(ActionError::NoLifeDue)?;
+
+    // 119.4. If a cost or effect allows a player to pay an amount of life
+    //        greater than 0, the player may do so only if their life total is
+    //        greater than or equal to the amount of the payment. [...]
+    let required = incomplete.life_paid() + PHYREXIAN_LIFE;
+    if player_data.life < required as i64 {
+        return Err(ActionError::NotEnoughLife {
+            required,
+            life: player_data.life,
+        });
+    }
+
+    incomplete.payments = payments;
+
+    Ok(())
 }
 
-pub fn finish_casting_spell(game: &mut Game, player: PlayerId, spell: Entity) {
-    let mut inner = || -> Result<(), String> {
-        {
-            let player_data = game
-                .players
-                .get_mut(player)
-                .ok_or("player is not a Player")?;
+/// Pay 2 life toward one of the Phyrexian mana symbols in a spell's total cost
+/// that hasn't been paid with life yet, moving the mana that's been paid to
+/// other parts of the cost if needed.
+pub(super) fn life_payment(
+    incomplete: &IncompleteSpell,
+    mana_pool: &ManaPool,
+) -> Option<Vec<CostItemPayment>> {
+    let mana_paid: Vec<_> = incomplete.mana_paid().collect();
+
+    let mut candidates: Vec<_> = incomplete
+        .total_cost
+        .items
+        .iter()
+        .zip(&incomplete.payments)
+        .enumerate()
+        .filter(|(_, (item, payment))| {
+            matches!(item, ManaCostItem::PhyrexianMana { .. }) && payment.life == 0
+        })
+        .map(|(index, (_, payment))| (index, payment.mana.len()))
+        .collect();
+
+    // Symbols that no mana has been paid toward are the easiest to pay with
+    // life instead.
+    candidates.sort_by_key(|&(_, mana)| mana);
+
+    candidates.into_iter().find_map(|(index, _)| {
+        let mut with_life = incomplete.clone();
+        with_life.payments[index] = CostItemPayment {
+            mana: Vec::new(),
+            life: PHYREXIAN_LIFE,
+        };
 
-            let spell_object = game
-                .world
-                .get::<Object>(spell)
-                .map_err(|_| "spell is not an Object")?;
+        assign_spell_mana(&with_life, mana_pool, &mana_paid, &[], false)
+    })
+}
 
-            let spell_incomplete = game
-                .world
-                .get::<IncompleteSpell>(spell)
-                .map_err(|_| "spell is not an IncompleteSpell")?;
+/// Work out which mana pays for each item of a spell's total cost. Every mana
+/// in `required` is spent, and mana in `optional` is spent only if it's needed
+/// to pay the cost in full. Items that were paid for with life keep that
+/// payment.
+pub(super) fn assign_spell_mana(
+    incomplete: &IncompleteSpell,
+    mana_pool: &ManaPool,
+    required: &[ManaId],
+    optional: &[ManaId],
+    pay_in_full: bool,
+) -> Option<Vec<CostItemPayment>> {
+    let unpaid: Vec<_> = (0..incomplete.payments.len())
+        .filter(|&index| incomplete.payments[index].life == 0)
+        .collect();
+    let items: Vec<_> = unpaid
+        .iter()
+        .map(|&index| incomplete.total_cost.items[index])
+        .collect();
+
+    let required = required.iter().map(|&id| (id, true));
+    let optional = optional
+        .iter()
+        .filter(|id| !incomplete.mana_paid().any(|paid| paid == **id))
+        .map(|&id| (id, false));
+    let mana: Vec<_> = required
+        .chain(optional)
+        .filter_map(|(id, required)| {
+            Some(AvailableMana {
+                id,
+                mana: mana_pool.get(id)?.clone(),
+                required,
+            })
+        })
+        .collect();
+
+    let assignment = mana_payment::solve(&items, &mana, pay_in_full)?;
+
+    let mut payments = incomplete.payments.clone();
+    for (index, mana) in unpaid.into_iter().zip(assignment) {
+        payments[index].mana = mana;
+    }
 
-            if spell_object.controller != Some(player) {
-                return Err("spell is not controlled by player".to_owned());
-            }
+    Some(payments)
+}
 
-            let mut mana_spent = HashSet::new();
+pub fn finish_casting_spell(
+    game: &mut Game,
+    player: PlayerId,
+    spell: Entity,
+) -> Result<(), ActionError> {
+    let life_paid = {
+        let player_data = game
+            .players
+            .get_mut(player)
+            .ok_or(ActionError::NoSuchPlayer { player })?;
 
-            for (i, cost) in spell_incomplete.total_cost.items.iter().enumerate() {
-                let mana_id = spell_incomplete
-                    .mana_paid
-                    .get(i)
-                    .ok_or_else(|| format!("Mana cost {} ({:?}) is not yet paid", i, cost))?;
+        let spell_object = game
+            .world
+            .get::<Object>(spell)
+            .map_err(|_| ActionError::NoSuchObject { object: spell })?;
 
-                if mana_spent.contains(mana_id) {
-                    return Err(format!("Mana {:?} was already spent", mana_id));
-                }
+        let spell_incomplete = game
+            .world
+            .get::<IncompleteSpell>(spell)
+            .map_err(|_| ActionError::NotCastingSpell { spell })?;
 
-                mana_spent.insert(*mana_id);
+        if spell_object.controller != Some(player) {
+            return Err(ActionError::NotControlled {
+                object: spell,
+                player,
+            });
+        }
 
-                let mana = player_data
-                    .mana_pool
-                    .get(*mana_id)
-                    .ok_or_else(|| format!("Mana {:?} was not valid", mana_id))?;
+        let mut mana_spent = HashSet::new();
+        let mana_paid: Vec<_> = spell_incomplete.mana_paid().collect();
 
-                if !cost.can_be_paid_with(&mana) {
-                    return Err(format!(
-                        "Mana {} ({:?}) cannot be paid with {:?}",
-                        i, cost, mana
-                    ));
-                }
+        for &mana_id in &mana_paid {
+            if !mana_spent.insert(mana_id) {
+                return Err(ActionError::ManaAlreadySpent { mana: mana_id });
             }
 
-            // 601.2h The player pays the total cost. First, they pay all costs
-            //        that don’t involve random elements or moving objects from
-            //        the library to a public zone, in any order. Then they pay
-            //        all remaining costs in any order. Partial payments are not
-            //        allowed. Unpayable costs can’t be paid.
-            player_data.mana_pool.spend(&spell_incomplete.mana_paid);
+            let mana = player_data
+                .mana_pool
+                .get(mana_id)
+                .ok_or(ActionError::NoSuchMana { mana: mana_id })?;
+
+            if !mana.can_be_spent_on(&spell_object) {
+                return Err(ActionError::ManaRestricted {
+                    mana: mana_id,
+                    spell,
+                });
+            }
         }
 
-        // 601.2i Once the steps described in 601.2a–h are completed,
-        //        effects that modify the characteristics of the spell as
-        //        it’s cast are applied, then the spell becomes cast. Any
-        //        abilities that trigger when a spell is cast or put onto
-        //        the stack trigger at this time. If the spell’s controller
-        //        had priority before casting it, they get priority.
-        //
-        // TODO: Spell modifications, triggers
-        game.world.remove_one::<IncompleteSpell>(spell).unwrap();
-        game.start_priority_round(player);
+        // The mana paid has to cover the whole cost, however it's divided
+        // between the cost's items.
+        assign_spell_mana(
+            &spell_incomplete,
+            &player_data.mana_pool,
+            &mana_paid,
+            &[],
+            true,
+        )
+        .ok_or(ActionError::CostNotPaid)?;
+
+        let life_paid = spell_incomplete.life_paid();
+        if player_data.life < life_paid as i64 {
+            return Err(ActionError::NotEnoughLife {
+                required: life_paid,
+                life: player_data.life,
+            });
+        }
+
+        // 601.2h The player pays the total cost. First, they pay all costs
+        //        that don’t involve random elements or moving objects from
+        //        the library to a public zone, in any order. Then they pay
+        //        all remaining costs in any order. Partial payments are not
+        //        allowed. Unpayable costs can’t be paid.
+        player_data.mana_pool.spend(&mana_paid);
 
-        Ok::<(), String>(())
+        life_paid
     };
 
-    if let Err(err) = inner() {
-        log::error!(
-            "Player {:?} cannot finish casting spell {:?}: {}",
-            player,
-            spell,
-            err
-        );
+    if life_paid > 0 {
+        game.change_life(player, -(life_paid as i64));
     }
-}
-
-pub fn cancel_casting_spell(game: &mut Game, player: PlayerId, spell: Entity) {
-    let mut inner = || {
-        let (current_zone, previous_zone) = {
-            let spell_entity = game.world.entity(spell).map_err(|_| "spell not found")?;
-
-            let spell_object = spell_entity
-                .get::<Object>()
-                .ok_or("spell was not an Object")?;
-
-            if spell_object.controller != Some(player) {
-                return Err("spell was not controlled by player");
-            }
 
-            let spell_incomplete = spell_entity
-                .get::<IncompleteSpell>()
-                .ok_or("not an incomplete spell")?;
+    // 601.2i Once the steps described in 601.2a–h are completed,
+    //        effects that modify the characteristics of the spell as
+    //        it’s cast are applied, then the spell becomes cast. Any
+    //        abilities that trigger when a spell is cast or put onto
+    //        the stack trigger at this time. If the spell’s controller
+    //        had priority before casting it, they get priority.
+    //
+    // TODO: Spell modifications, triggers
+    game.world.remove_one::<IncompleteSpell>(spell).unwrap();
+    game.spell_proposals
+        .retain(|(proposed, _)| *proposed != spell);
+    game.emit(GameEvent::SpellCast { player, spell });
+    game.start_priority_round(player);
+
+    Ok(())
+}
 
-            (spell_object.zone, spell_incomplete.previous_zone)
-        };
+/// Stop casting a spell, returning the game to the moment before the casting
+/// of that spell was proposed.
+pub fn cancel_casting_spell(
+    game: &mut Game,
+    player: PlayerId,
+    spell: Entity,
+) -> Result<(), ActionError> {
+    {
+        let spell_entity = game
+            .world
+            .entity(spell)
+            .map_err(|_| ActionError::NoSuchObject { object: spell })?;
 
-        game.world.remove_one::<IncompleteSpell>(spell).unwrap();
+        let spell_object = spell_entity
+            .get::<Object>()
+            .ok_or(ActionError::NoSuchObject { object: spell })?;
 
-        if current_zone != previous_zone {
-            game.move_object_to_zone(spell, previous_zone);
+        if spell_object.controller != Some(player) {
+            return Err(ActionError::NotControlled {
+                object: spell,
+                player,
+            });
         }
 
-        Ok::<(), &str>(())
-    };
+        if !spell_entity.has::<IncompleteSpell>() {
+            return Err(ActionError::NotCastingSpell { spell });
+        }
+    }
 
-    if let Err(err) = inner() {
-        log::info!(
-            "Player {:?} cannot cancel casting spell {:?}: {}",
-            player,
-            spell,
-            err
-        );
+    let index = game
+        .spell_proposals
+        .iter()
+        .position(|(proposed, _)| *proposed == spell)
+        .ok_or(ActionError::NotCastingSpell { spell })?;
+
+    // Any spells proposed after this one are part of the moment being
+    // reversed, so they're undone along with it.
+    let (_, before) = game.spell_proposals.drain(index..).next().unwrap();
+    game.restore(before);
+
+    // The spell returns to the zone it came from.
+    let returned_to = game.world.get::<Object>(spell).map(|object| object.zone);
+    if let Ok(zone) = returned_to {
+        game.emit(GameEvent::ZoneChange {
+            object: spell,
+            from: ZoneId::Stack,
+            to: zone,
+        });
     }
 
-    game.give_priority(player);
+    Ok(())
 }
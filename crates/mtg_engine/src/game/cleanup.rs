@@ -0,0 +1,146 @@
+//! 514. Cleanup Step
+
+use std::collections::HashSet;
+
+use hecs::Entity;
+
+use crate::action::{ActionError, PlayerActionCategory};
+use crate::components::{Damage, Object, UntilEotEffect};
+use crate::player::PlayerId;
+use crate::queries::QueryMaxHandSize;
+use crate::zone::ZoneId;
+
+use super::{Game, GameState};
+
+pub fn enter_cleanup(game: &mut Game) {
+    game.repeat_cleanup_step = false;
+
+    // 514.1. First, if the active player’s hand contains more cards than their
+    //        maximum hand size (normally seven), they discard enough cards to
+    //        reduce their hand size to that number. This turn-based action
+    //        doesn’t use the stack.
+    if cards_to_discard(game, game.active_player) > 0 {
+        game.state = GameState::Player {
+            player: game.active_player,
+            action: PlayerActionCategory::DiscardToHandSize,
+        };
+
+        // ...continues in `discard_to_hand_size`
+        return;
+    }
+
+    finish_cleanup(game);
+}
+
+/// Returns how many cards a player must discard to reduce their hand size to
+/// their maximum hand size.
+pub fn cards_to_discard(game: &Game, player: PlayerId) -> usize {
+    let hand_size = game.zone(ZoneId::Hand(player)).unwrap().members().len();
+
+    match game.query(QueryMaxHandSize(player)) {
+        Some(max_hand_size) => hand_size.saturating_sub(max_hand_size as usize),
+        None => 0,
+    }
+}
+
+fn discard_valid(game: &Game, player: PlayerId, cards: &[Entity]) -> Result<(), ActionError> {
+    let required = cards_to_discard(game, player);
+    if cards.len() != required {
+        return Err(ActionError::WrongDiscardCount {
+            required,
+            chosen: cards.len(),
+        });
+    }
+
+    let mut seen = HashSet::new();
+    for &card in cards {
+        if !seen.insert(card) {
+            return Err(ActionError::DiscardChosenTwice { card });
+        }
+
+        let object = game
+            .world
+            .get::<Object>(card)
+            .map_err(|_| ActionError::NoSuchObject { object: card })?;
+
+        if object.zone != ZoneId::Hand(player) {
+            return Err(ActionError::DiscardNotInHand { card });
+        }
+    }
+
+    Ok(())
+}
+
+pub fn discard_to_hand_size(
+    game: &mut Game,
+    player: PlayerId,
+    cards: &[Entity],
+) -> Result<(), ActionError> {
+    let required_state = GameState::Player {
+        player,
+        action: PlayerActionCategory::DiscardToHandSize,
+    };
+
+    if game.state != required_state {
+        return Err(ActionError::NotDiscardingToHandSize);
+    }
+
+    discard_valid(game, player, cards)?;
+
+    for &card in cards {
+        game.discard(card);
+    }
+
+    finish_cleanup(game);
+
+    Ok(())
+}
+
+pub fn finish_cleanup(game: &mut Game) {
+    // 514.2. Second, the following actions happen simultaneously: all damage
+    //        marked on permanents (including phased-out permanents) is removed
+    //        and all “until end of turn” and “this turn” effects end. This
+    //        turn-based action doesn’t use the stack.
+    let mut damage_to_remove = Vec::new();
+    for (entity, _damage) in game.world.query_mut::<(&Damage,)>() {
+        damage_to_remove.push(entity);
+    }
+
+    for entity in damage_to_remove {
+        let _ = game.world.remove_one::<Damage>(entity);
+    }
+
+    let mut to_despawn = Vec::new();
+    for (entity, _effect) in game.world.query_mut::<(&UntilEotEffect,)>() {
+        to_despawn.push(entity);
+    }
+
+    for entity in to_despawn {
+        let _ = game.world.despawn(entity);
+    }
+
+    // 514.3. Normally, no player receives priority during the cleanup step, so
+    //        no spells can be cast and no abilities can be activated. However,
+    //        this rule is subject to the following exception:
+    // 514.3a At this point, the game checks to see if any state-based actions
+    //        would be performed and/or any triggered abilities are waiting to
+    //        be put onto the stack (including those that trigger “at the
+    //        beginning of the next cleanup step”). If so, those state-based
+    //        actions are performed, then those triggered abilities are put on
+    //        the stack, then the active player gets priority. Players may cast
+    //        spells and activate abilities. Once the stack is empty and all
+    //        players pass in succession, another cleanup step begins.
+    let actions_performed = game.apply_state_based_actions();
+
+    if matches!(game.state, GameState::Complete(_)) {
+        return;
+    }
+
+    if actions_performed || !game.pending_triggers.is_empty() {
+        game.repeat_cleanup_step = true;
+        game.start_priority_round(game.active_player);
+        return;
+    }
+
+    game.end_current_step();
+}
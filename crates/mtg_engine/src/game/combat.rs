@@ -1,7 +1,11 @@
+use std::collections::HashSet;
+
 use hecs::Entity;
 
-use crate::action::PlayerActionCategory;
-use crate::components::Object;
+use crate::action::{ActionError, AttackDeclaration, BlockDeclaration, PlayerActionCategory};
+use crate::components::{Attacking, Blocking, Damage, Object};
+use crate::queries::QueryPt;
+use crate::target::Target;
 use crate::types::CardType;
 use crate::{components::Permanent, player::PlayerId};
 
@@ -34,6 +38,14 @@ pub fn enter_declare_attackers(game: &mut Game) {
     //        illegal; the game returns to the moment before the
     //        declaration (see rule 726, “Handling Illegal
     //        Actions”).
+    //
+    // 800.4j If a player leaves the game during their turn, that turn
+    //        continues to its completion without an active player. [...]
+    if !game.players.is_in_game(game.active_player) {
+        game.start_priority_round(game.active_player);
+        return;
+    }
+
     game.state = GameState::Player {
         player: game.active_player,
         action: PlayerActionCategory::ChooseAttackers,
@@ -42,10 +54,6 @@ pub fn enter_declare_attackers(game: &mut Game) {
 
 /// 509. Declare Blockers Step
 pub fn enter_declare_blockers(game: &mut Game) {
-    // TODO: Choose player who is defender instead of just "not the
-    // active player"
-    let nap = game.players.player_after(game.active_player);
-
     // 509.1. First, the defending player declares blockers. This
     //        turn-based action doesn’t use the stack. To declare
     //        blockers, the defending player follows the steps
@@ -55,10 +63,9 @@ pub fn enter_declare_blockers(game: &mut Game) {
     //        the declaration is illegal; the game returns to the
     //        moment before the declaration (see rule 726, “Handling
     //        Illegal Actions”).
-    game.state = GameState::Player {
-        player: nap,
-        action: PlayerActionCategory::ChooseBlockers,
-    };
+    //
+    // ...continues in `choose_blockers` for each defending player.
+    ask_next_defender(game, game.active_player);
 
     // 509.2. Second, for each attacking creature that’s become
     //        blocked, the active player announces that creature’s
@@ -96,6 +103,72 @@ pub fn enter_declare_blockers(game: &mut Game) {
     // TODO
 }
 
+/// 802.2. As the combat phase starts, the attacking player doesn’t choose an
+///        opponent to become the defending player. Instead, all the attacking
+///        player’s opponents become defending players during the combat
+///        phase.
+///
+/// Each defending player that is being attacked declares blockers in turn
+/// order, starting after the active player. A player can only block creatures
+/// attacking them or their planeswalkers, so the declarations don't depend on
+/// each other. Once every defending player has declared blockers, the active
+/// player gets priority.
+///
+/// The next defender is the first one after `previous` in turn order, so
+/// `previous` may be a defender who has just left the game.
+pub fn ask_next_defender(game: &mut Game, previous: PlayerId) {
+    let num_players = game.players.len();
+    let after_active = |player: PlayerId| {
+        let active = game.turn_order_position(game.active_player);
+        (game.turn_order_position(player) + num_players - active) % num_players
+    };
+
+    let next = attacked_players(game)
+        .into_iter()
+        .find(|&defender| after_active(defender) > after_active(previous));
+
+    match next {
+        Some(player) => {
+            game.state = GameState::Player {
+                player,
+                action: PlayerActionCategory::ChooseBlockers,
+            };
+        }
+        None => game.start_priority_round(game.active_player),
+    }
+}
+
+/// The players still in the game that have creatures attacking them or their
+/// planeswalkers, in turn order after the active player.
+fn attacked_players(game: &Game) -> Vec<PlayerId> {
+    let mut attacked = HashSet::new();
+    for (_, (attacking,)) in game.world.query::<(&Attacking,)>().iter() {
+        if let Some(defender) = defending_player(game, attacking.target) {
+            attacked.insert(defender);
+        }
+    }
+
+    let mut players = Vec::new();
+    let mut player = game.active_player;
+    for _ in 0..game.players.len() {
+        player = game.players.player_after(player);
+        if attacked.contains(&player) && !players.contains(&player) {
+            players.push(player);
+        }
+    }
+
+    players
+}
+
+/// The player being attacked by a creature that's attacking the given player or
+/// planeswalker.
+pub fn defending_player(game: &Game, target: Target) -> Option<PlayerId> {
+    match target {
+        Target::Player(player) => Some(player),
+        Target::Object(planeswalker) => game.world.get::<Object>(planeswalker).ok()?.controller,
+    }
+}
+
 // 510. Combat Damage Step
 pub fn enter_combat_damage(game: &mut Game) {
     // 510.1. First, the active player announces how each attacking
@@ -113,7 +186,78 @@ pub fn enter_combat_damage(game: &mut Game) {
     //        or activate abilities between the time combat damage
     //        is assigned and the time it’s dealt.
     //
-    // TODO
+    // TODO: Let players choose how damage is assigned instead of assigning
+    // lethal damage to each blocker in order.
+    let mut assignments = Vec::new();
+    {
+        let mut query = game.world.query::<(&Attacking,)>();
+        for (attacker, (attacking,)) in query.iter() {
+            let power = match game.query(QueryPt(attacker)) {
+                Some(pt) if pt.power > 0 => pt.power as u64,
+                _ => continue,
+            };
+
+            // 510.1a Each attacking creature and each blocking creature
+            //        assigns combat damage equal to its power. [...]
+            //
+            // 510.1b An unblocked creature assigns its combat damage to the
+            //        player, planeswalker, or battle it’s attacking. [...]
+            if !attacking.blocked {
+                assignments.push((attacker, attacking.target, power));
+                continue;
+            }
+
+            // 510.1c A blocked creature assigns its combat damage to the
+            //        creatures blocking it. If no creatures are currently
+            //        blocking it (if, for example, they were destroyed or
+            //        removed from combat), it assigns no combat damage. If
+            //        exactly one creature is blocking it, it assigns all its
+            //        combat damage to that creature. If two or more creatures
+            //        are blocking it, it assigns its combat damage to those
+            //        creatures according to the damage assignment order
+            //        announced for it. [...]
+            let blockers: Vec<_> = attacking
+                .blockers
+                .iter()
+                .copied()
+                .filter(|&blocker| is_blocking(game, blocker, attacker))
+                .collect();
+
+            let mut remaining = power;
+            for (index, &blocker) in blockers.iter().enumerate() {
+                let amount = if index == blockers.len() - 1 {
+                    remaining
+                } else {
+                    remaining.min(lethal_damage(game, blocker))
+                };
+
+                assignments.push((attacker, Target::Object(blocker), amount));
+                remaining -= amount;
+            }
+        }
+
+        // 510.1d A blocking creature assigns combat damage to the creatures
+        //        it’s blocking. If it isn’t currently blocking any creatures
+        //        (if, for example, they were destroyed or removed from
+        //        combat), it assigns no combat damage. [...]
+        let mut query = game.world.query::<(&Blocking,)>();
+        for (blocker, (blocking,)) in query.iter() {
+            if game.world.get::<Attacking>(blocking.attacker).is_err() {
+                continue;
+            }
+
+            match game.query(QueryPt(blocker)) {
+                Some(pt) if pt.power > 0 => {
+                    assignments.push((blocker, Target::Object(blocking.attacker), pt.power as u64))
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    for (source, target, amount) in assignments {
+        super::damage::deal_damage(game, source, target, amount, true);
+    }
 
     // 510.3. Third, the active player gets priority. (See rule 117, “Timing and Priority.”)
     //
@@ -139,64 +283,153 @@ pub fn enter_end_combat(game: &mut Game) {
     //        phase.
     //
     // TODO
+}
 
-    // 511.3. As soon as the end of combat step ends, all creatures
-    //        and planeswalkers are removed from combat. After the
-    //        end of combat step ends, the combat phase is over and
-    //        the postcombat main phase begins (see rule 505).
-    //
-    // TODO
+/// 511.3. As soon as the end of combat step ends, all creatures and
+///        planeswalkers are removed from combat. After the end of combat step
+///        ends, the combat phase is over and the postcombat main phase begins
+///        (see rule 505).
+pub fn leave_end_combat(game: &mut Game) {
+    let mut in_combat = Vec::new();
+    for (entity, _attacking) in game.world.query_mut::<(&Attacking,)>() {
+        in_combat.push(entity);
+    }
+    for (entity, _blocking) in game.world.query_mut::<(&Blocking,)>() {
+        in_combat.push(entity);
+    }
+
+    for entity in in_combat {
+        remove_from_combat(game, entity);
+    }
+}
+
+/// 506.4. A permanent that’s removed from combat stops being an attacking,
+///        blocking, blocked, and/or unblocked creature.
+pub fn remove_from_combat(game: &mut Game, permanent: Entity) {
+    let _ = game.world.remove_one::<Attacking>(permanent);
+    let _ = game.world.remove_one::<Blocking>(permanent);
+}
+
+fn is_blocking(game: &Game, blocker: Entity, attacker: Entity) -> bool {
+    match game.world.get::<Blocking>(blocker) {
+        Ok(blocking) => blocking.attacker == attacker,
+        Err(_) => false,
+    }
+}
+
+/// 510.1c [...] When checking for assigned lethal damage, take into account
+///        damage already marked on the creature and damage from other
+///        creatures that’s being assigned during the same combat damage step,
+///        but not any abilities or effects that might change the amount of
+///        damage that’s actually dealt. [...]
+fn lethal_damage(game: &Game, creature: Entity) -> u64 {
+    let toughness = match game.query(QueryPt(creature)) {
+        Some(pt) => pt.toughness.max(0) as u64,
+        None => return 0,
+    };
+
+    let marked = match game.world.get::<Damage>(creature) {
+        Ok(damage) => damage.amount,
+        Err(_) => 0,
+    };
+
+    toughness.saturating_sub(marked)
 }
 
-fn attackers_valid(game: &Game, player: PlayerId, attackers: &[Entity]) -> Result<(), String> {
+/// 508.1b [...] the active player announces which player or planeswalker each
+///        of the chosen creatures is attacking.
+///
+/// Creatures can attack any opponent still in the game, or any planeswalker
+/// that one of them controls.
+pub fn can_attack(game: &Game, player: PlayerId, target: Target) -> bool {
+    let defender = match target {
+        Target::Player(defender) => defender,
+        Target::Object(planeswalker) => {
+            let entity = match game.world.entity(planeswalker) {
+                Ok(entity) => entity,
+                Err(_) => return false,
+            };
+
+            let object = match entity.get::<Object>() {
+                Some(object) => object,
+                None => return false,
+            };
+
+            if entity.get::<Permanent>().is_none()
+                || !object.types.contains(&CardType::Planeswalker)
+            {
+                return false;
+            }
+
+            match object.controller {
+                Some(controller) => controller,
+                None => return false,
+            }
+        }
+    };
+
+    defender != player && game.players.is_in_game(defender)
+}
+
+fn attackers_valid(
+    game: &Game,
+    player: PlayerId,
+    attackers: &[AttackDeclaration],
+) -> Result<(), ActionError> {
     // 508. Declare Attackers Step
 
     // 508.1a The active player chooses which creatures that they control,
     //        if any, will attack. The chosen creatures must be untapped,
     //        and each one must either have haste or have been controlled by
     //        the active player continuously since the turn began.
-    for &attacker in attackers {
+    let mut seen = HashSet::new();
+    for &AttackDeclaration { attacker, target } in attackers {
+        if !seen.insert(attacker) {
+            return Err(ActionError::AttackerChosenTwice { attacker });
+        }
+
         let entity = game
             .world
             .entity(attacker)
-            .map_err(|_| format!("Entity {:?} did not exist", attacker))?;
+            .map_err(|_| ActionError::NoSuchObject { object: attacker })?;
 
         let object = entity
             .get::<Object>()
-            .ok_or_else(|| format!("Entity {:?} is not an Object", attacker))?;
+            .ok_or(ActionError::NoSuchObject { object: attacker })?;
 
         if object.controller != Some(player) {
-            return Err(format!(
-                "Entity {:?} is not controlled by {:?}",
-                attacker, player
-            ));
+            return Err(ActionError::NotControlled {
+                object: attacker,
+                player,
+            });
         }
 
         // FIXME: Use type query instead to figure out whether something is
         // a permanent.
         let permanent = entity
             .get::<Permanent>()
-            .ok_or_else(|| format!("Entity {:?} is not a permanent", attacker))?;
+            .ok_or(ActionError::AttackerNotCreature { attacker })?;
 
         // FIXME: Use type query instead to figure out whether something is
         // a creature.
         if !object.types.contains(&CardType::Creature) {
-            return Err(format!("Entity {:?} is not a creature", attacker));
+            return Err(ActionError::AttackerNotCreature { attacker });
         }
 
         if permanent.tapped {
-            return Err(format!("Creature {:?} is tapped", attacker));
+            return Err(ActionError::AttackerTapped { attacker });
         }
 
         // TODO: Check for control timestamp or haste.
-    }
 
-    // 508.1b If the defending player controls any planeswalkers, or the
-    //        game allows the active player to attack multiple other
-    //        players, the active player announces which player or
-    //        planeswalker each of the chosen creatures is attacking.
-    //
-    // TODO: This should be part of the declaration information.
+        // 508.1b If the defending player controls any planeswalkers, or the
+        //        game allows the active player to attack multiple other
+        //        players, the active player announces which player or
+        //        planeswalker each of the chosen creatures is attacking.
+        if !can_attack(game, player, target) {
+            return Err(ActionError::InvalidAttackTarget { attacker, target });
+        }
+    }
 
     // 508.1c The active player checks each creature they control to see
     //        whether it’s affected by any restrictions (effects that say a
@@ -230,7 +463,11 @@ fn attackers_valid(game: &Game, player: PlayerId, attackers: &[Entity]) -> Resul
     Ok(())
 }
 
-pub fn choose_attackers(game: &mut Game, player: PlayerId, attackers: &[Entity]) {
+pub fn choose_attackers(
+    game: &mut Game,
+    player: PlayerId,
+    attackers: &[AttackDeclaration],
+) -> Result<(), ActionError> {
     log::info!("Player {:?} chose attackers {:?}", player, attackers);
 
     let required_state = GameState::Player {
@@ -239,14 +476,10 @@ pub fn choose_attackers(game: &mut Game, player: PlayerId, attackers: &[Entity])
     };
 
     if game.state != required_state {
-        log::warn!("Player {:?} cannot choose attackers right now.", player);
-        return;
+        return Err(ActionError::NotDeclaringAttackers);
     }
 
-    if let Err(reason) = attackers_valid(game, player, attackers) {
-        log::warn!("Attackers were not valid: {}", reason);
-        return;
-    }
+    attackers_valid(game, player, attackers)?;
 
     // 508.1e If any of the chosen creatures have banding or a “bands with
     //        other” ability, the active player announces which creatures,
@@ -257,8 +490,11 @@ pub fn choose_attackers(game: &mut Game, player: PlayerId, attackers: &[Entity])
     // 508.1f The active player taps the chosen creatures. Tapping a
     //        creature when it’s declared as an attacker isn’t a cost;
     //        attacking simply causes creatures to become tapped.
-    for &attacker in attackers {
-        let mut permanent = game.world.get_mut::<Permanent>(attacker).unwrap();
+    for declaration in attackers {
+        let mut permanent = game
+            .world
+            .get_mut::<Permanent>(declaration.attacker)
+            .unwrap();
         permanent.tapped = true;
     }
 
@@ -294,8 +530,18 @@ pub fn choose_attackers(game: &mut Game, player: PlayerId, attackers: &[Entity])
     //        becomes an attacking creature. It remains an attacking
     //        creature until it’s removed from combat or the combat phase
     //        ends, whichever comes first. See rule 506.4.
-    //
-    // TODO
+    for &AttackDeclaration { attacker, target } in attackers {
+        game.world
+            .insert_one(
+                attacker,
+                Attacking {
+                    target,
+                    blocked: false,
+                    blockers: Vec::new(),
+                },
+            )
+            .unwrap();
+    }
 
     // 508.1m Any abilities that trigger on attackers being declared
     //        trigger.
@@ -304,29 +550,121 @@ pub fn choose_attackers(game: &mut Game, player: PlayerId, attackers: &[Entity])
     // 508.2. Second, the active player gets priority. (See rule 117,
     //        “Timing and Priority.”)
     game.start_priority_round(game.active_player);
+
+    Ok(())
 }
 
-fn blockers_valid(_game: &Game, _player: PlayerId, _blockers: &[Entity]) -> Result<(), String> {
+fn blockers_valid(
+    game: &Game,
+    player: PlayerId,
+    blockers: &[BlockDeclaration],
+) -> Result<(), ActionError> {
+    // 509.1a The defending player chooses which creatures they control, if
+    //        any, will block. The chosen creatures must be untapped. For each
+    //        of the chosen creatures, the defending player chooses one
+    //        creature for it to block that’s attacking that player or a
+    //        planeswalker that player controls.
+    let mut seen = HashSet::new();
+    for &BlockDeclaration { blocker, attacker } in blockers {
+        if !seen.insert(blocker) {
+            return Err(ActionError::BlockerChosenTwice { blocker });
+        }
+
+        let entity = game
+            .world
+            .entity(blocker)
+            .map_err(|_| ActionError::NoSuchObject { object: blocker })?;
+
+        let object = entity
+            .get::<Object>()
+            .ok_or(ActionError::NoSuchObject { object: blocker })?;
+
+        if object.controller != Some(player) {
+            return Err(ActionError::NotControlled {
+                object: blocker,
+                player,
+            });
+        }
+
+        let permanent = entity
+            .get::<Permanent>()
+            .ok_or(ActionError::BlockerNotCreature { blocker })?;
+
+        if !object.types.contains(&CardType::Creature) {
+            return Err(ActionError::BlockerNotCreature { blocker });
+        }
+
+        if permanent.tapped {
+            return Err(ActionError::BlockerTapped { blocker });
+        }
+
+        let attacked = match game.world.get::<Attacking>(attacker) {
+            Ok(attacking) => defending_player(game, attacking.target) == Some(player),
+            Err(_) => false,
+        };
+
+        if !attacked {
+            return Err(ActionError::CannotBlockAttacker { blocker, attacker });
+        }
+    }
+
+    // 509.1b The defending player checks each creature they control to see
+    //        whether it’s affected by any restrictions (effects that say a
+    //        creature can’t block, or that it can’t block unless some
+    //        condition is met). If any restrictions are being disobeyed, the
+    //        declaration of blockers is illegal.
+    //
+    // TODO
+
+    // 509.1c The defending player checks each creature they control to see
+    //        whether it’s affected by any requirements (effects that say a
+    //        creature must block, or that it must block if some condition is
+    //        met). [...]
+    //
+    // TODO
+
     Ok(())
 }
 
-pub fn choose_blockers(game: &mut Game, player: PlayerId, blockers: &[Entity]) {
+pub fn choose_blockers(
+    game: &mut Game,
+    player: PlayerId,
+    blockers: &[BlockDeclaration],
+) -> Result<(), ActionError> {
+    log::info!("Player {:?} chose blockers {:?}", player, blockers);
+
     let required_state = GameState::Player {
         player,
         action: PlayerActionCategory::ChooseBlockers,
     };
 
     if game.state != required_state {
-        log::warn!("Player {:?} cannot choose blockers right now.", player);
-        return;
+        return Err(ActionError::NotDeclaringBlockers);
     }
 
-    if let Err(reason) = blockers_valid(game, player, blockers) {
-        log::warn!("Blockers were not valid: {}", reason);
-        return;
+    blockers_valid(game, player, blockers)?;
+
+    // 509.1g Each chosen creature still controlled by the defending player
+    //        becomes a blocking creature. Each one is blocking the attacking
+    //        creatures chosen for it. It remains a blocking creature until
+    //        it’s removed from combat or the combat phase ends, whichever
+    //        comes first. See rule 506.4.
+    //
+    // 509.1h An attacking creature with one or more creatures declared as
+    //        blockers for it becomes a blocked creature; one with no
+    //        creatures declared as blockers for it becomes an unblocked
+    //        creature. [...]
+    for &BlockDeclaration { blocker, attacker } in blockers {
+        game.world
+            .insert_one(blocker, Blocking { attacker })
+            .unwrap();
+
+        let mut attacking = game.world.get_mut::<Attacking>(attacker).unwrap();
+        attacking.blocked = true;
+        attacking.blockers.push(blocker);
     }
 
-    // TODO
+    ask_next_defender(game, player);
 
-    game.start_priority_round(game.active_player);
+    Ok(())
 }
@@ -0,0 +1,81 @@
+//! Describes how a game is set up before it begins.
+
+use serde::{Deserialize, Serialize};
+
+use crate::object_db::CardId;
+
+/// Everything needed to set up and start a game. See [`Game::new`].
+///
+/// [`Game::new`]: super::Game::new
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// The players in the game, in turn order.
+    pub players: Vec<PlayerConfig>,
+
+    /// 103.3. Each player begins the game with a starting life total of 20.
+    ///        Some variant games have different starting life totals.
+    pub starting_life: i64,
+
+    /// 103.4. Each player draws a number of cards equal to their starting hand
+    ///        size, which is normally seven. [...]
+    pub starting_hand_size: usize,
+
+    /// 103.4c In a multiplayer game and in any Brawl game, the first mulligan
+    ///        a player takes doesn’t count toward the number of cards that
+    ///        player will put on the bottom of their library. [...]
+    pub free_first_mulligan: bool,
+
+    /// 103.7a In a two-player game, the player who plays first skips the draw
+    ///        step (see rule 504, “Draw Step”) of their first turn.
+    ///
+    /// 103.7c In all other multiplayer games, no player skips the draw step of
+    ///        their first turn.
+    pub starting_player_skips_draw: bool,
+
+    /// The seed for the game's random number generator, or `None` to choose
+    /// one at random.
+    pub seed: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerConfig {
+    pub name: String,
+
+    /// The cards in the player's deck, which becomes their library as the game
+    /// starts.
+    pub deck: Vec<CardId>,
+}
+
+impl GameConfig {
+    /// The usual setup for a game with the given number of players, who all
+    /// have empty decks.
+    pub fn new(num_players: u32) -> Self {
+        let players = (0..num_players)
+            .map(|i| PlayerConfig {
+                name: format!("Player {}", i + 1),
+                deck: Vec::new(),
+            })
+            .collect();
+
+        Self {
+            players,
+            starting_life: 20,
+            starting_hand_size: 7,
+            free_first_mulligan: num_players > 2,
+            starting_player_skips_draw: num_players == 2,
+            seed: None,
+        }
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl Default for GameConfig {
+    /// A two-player game.
+    fn default() -> Self {
+        Self::new(2)
+    }
+}
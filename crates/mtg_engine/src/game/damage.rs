@@ -0,0 +1,133 @@
+//! 120. Damage
+//!
+//! 701.7. Destroy
+//!
+//! 701.15. Regenerate
+
+use hecs::Entity;
+
+use crate::components::{Damage, Object, Permanent};
+use crate::event::GameEvent;
+use crate::replacement::ReplaceableEvent;
+use crate::target::Target;
+use crate::types::CardType;
+use crate::zone::ZoneId;
+
+use super::Game;
+
+/// Deal damage from a source to a player or permanent, giving replacement and
+/// prevention effects the chance to modify it first.
+pub fn deal_damage(game: &mut Game, source: Entity, target: Target, amount: u64, combat: bool) {
+    super::replacement::perform(
+        game,
+        ReplaceableEvent::Damage {
+            source,
+            target,
+            amount,
+            combat,
+        },
+    );
+}
+
+/// Deal damage after any replacement and prevention effects have been applied
+/// to it.
+pub fn apply_damage(game: &mut Game, source: Entity, target: Target, amount: u64, combat: bool) {
+    // 120.8. If a source would deal 0 damage, it does not deal damage at all.
+    if amount == 0 {
+        return;
+    }
+
+    log::debug!("{:?} deals {} damage to {:?}", source, amount, target);
+
+    match target {
+        // 120.3a Damage dealt to a player by a source without infect causes
+        //        that player to lose that much life.
+        Target::Player(player) => {
+            if game.players.get(player).is_none() {
+                return;
+            }
+
+            game.emit(GameEvent::DamageDealt {
+                source,
+                target,
+                amount,
+                combat,
+            });
+            game.change_life(player, -(amount as i64));
+        }
+
+        Target::Object(object) => {
+            // Only permanents can be dealt damage.
+            if game.world.get::<Permanent>(object).is_err() {
+                return;
+            }
+
+            let is_creature = match game.world.get::<Object>(object) {
+                Ok(object) => object.types.contains(&CardType::Creature),
+                Err(_) => return,
+            };
+
+            game.emit(GameEvent::DamageDealt {
+                source,
+                target,
+                amount,
+                combat,
+            });
+
+            // 120.3c Damage dealt to a planeswalker causes that many loyalty
+            //        counters to be removed from that planeswalker.
+            //
+            // TODO
+
+            // 120.3e Damage dealt to a creature by a source with neither wither
+            //        nor infect causes that much damage to be marked on that
+            //        creature.
+            if is_creature {
+                let marked = match game.world.get_mut::<Damage>(object) {
+                    Ok(mut damage) => {
+                        damage.amount += amount;
+                        true
+                    }
+                    Err(_) => false,
+                };
+
+                if !marked {
+                    game.world.insert_one(object, Damage { amount }).unwrap();
+                }
+            }
+        }
+    }
+}
+
+/// 701.7a To destroy a permanent, move it from the battlefield to its owner’s
+///        graveyard.
+pub fn destroy(game: &mut Game, permanent: Entity) {
+    super::replacement::perform(game, ReplaceableEvent::Destroy { permanent });
+}
+
+/// Destroy a permanent after any replacement effects, like regeneration, have
+/// been applied.
+pub fn apply_destroy(game: &mut Game, permanent: Entity) {
+    // TODO: 702.12b A permanent with indestructible can’t be destroyed.
+    let owner = match game.world.get::<Object>(permanent) {
+        Ok(object) => object.owner,
+        Err(_) => return,
+    };
+
+    log::debug!("Destroying {:?}", permanent);
+    game.move_object_to_zone(permanent, ZoneId::Graveyard(owner));
+}
+
+/// 701.15a [...] instead remove all damage marked on it and its controller
+///         taps it. If it’s an attacking or blocking creature, remove it from
+///         combat.
+pub fn regenerate(game: &mut Game, permanent: Entity) {
+    log::debug!("Regenerating {:?}", permanent);
+
+    let _ = game.world.remove_one::<Damage>(permanent);
+    super::combat::remove_from_combat(game, permanent);
+
+    if let Ok(mut state) = game.world.get_mut::<Permanent>(permanent) {
+        state.tapped = true;
+    }
+}
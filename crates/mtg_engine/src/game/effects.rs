@@ -0,0 +1,64 @@
+//! 609. Effects
+
+use crate::components::UntilEotEffect;
+use crate::effect::Effect;
+use crate::player::PlayerId;
+use crate::replacement::ReplacementEffect;
+use crate::zone::ZoneId;
+
+use super::Game;
+
+/// Perform the given effects in order on behalf of the controller of the spell
+/// or ability that is resolving.
+pub fn apply(game: &mut Game, controller: PlayerId, effects: &[Effect]) {
+    for effect in effects {
+        log::debug!("Player {:?} applying effect {:?}", controller, effect);
+
+        match effect {
+            Effect::DrawCards { count } => {
+                for _ in 0..*count {
+                    game.draw_card(controller);
+                }
+            }
+
+            Effect::GainLife { amount } => {
+                game.change_life(controller, *amount);
+            }
+
+            Effect::DiscardHand => {
+                let hand = game.zone(ZoneId::Hand(controller)).unwrap();
+                for card in hand.members().to_vec() {
+                    game.discard(card);
+                }
+            }
+
+            Effect::ExileHandAndDrawThatMany => {
+                let hand = game.zone(ZoneId::Hand(controller)).unwrap();
+                let cards = hand.members().to_vec();
+                for &card in &cards {
+                    game.move_object_to_zone(card, ZoneId::Exile);
+                }
+
+                for _ in 0..cards.len() {
+                    game.draw_card(controller);
+                }
+            }
+
+            Effect::DiscardAtRandom { count } => {
+                game.discard_at_random(controller, *count as usize);
+            }
+
+            Effect::FlipCoin { won, lost } => {
+                if game.flip_coin(controller) {
+                    apply(game, controller, won);
+                } else {
+                    apply(game, controller, lost);
+                }
+            }
+
+            Effect::PreventAllCombatDamage => {
+                game.add_effect((ReplacementEffect::PreventAllCombatDamage, UntilEotEffect));
+            }
+        }
+    }
+}
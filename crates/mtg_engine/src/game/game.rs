@@ -1,17 +1,33 @@
 //! Defines the high-level structure describing a game of Magic.
 
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::fmt::{self, Debug};
 
-use hecs::{Entity, EntityBuilder, World};
+use hecs::{DynamicBundle, Entity, EntityBuilder, World};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 
+use super::config::GameConfig;
+use super::replacement::PendingReplacement;
+use super::replay::{ActionLog, Checkpoint, Divergence};
+use super::save::LoadError;
+use super::snapshot::GameSnapshot;
+use super::start::{Mulligans, StartError};
+use super::view::{PlayerView, Viewer};
 use crate::player::{PlayerId, Players};
 use crate::{
-    action::{PlayerAction, PlayerActionCategory},
-    components::{Card, Damage, Object, Permanent, UntilEotEffect},
+    action::{ActionError, PlayerAction, PlayerActionCategory},
+    components::{
+        Card, Counters, Object, Permanent, Revealed, Timestamp, TriggeredAbility, UntilEotEffect,
+    },
+    deck::Deck,
+    event::GameEvent,
+    mana_pool::{Mana, ManaId, UnspentManaEffect},
     object_db::{CardId, ObjectDb},
     queries::Query,
+    replacement::ReplaceableEvent,
+    target::Target,
     types::CardType,
     zone::{Zone, ZoneId},
 };
@@ -24,20 +40,39 @@ pub struct Game {
     /// active effects and anything that can be targeted.
     pub(super) world: World,
 
-    /// The next timestamp that will be assigned to an entity.
-    next_timestamp: u64,
+    /// The next timestamp that will be assigned to an effect.
+    pub(super) next_timestamp: u64,
 
     pub(super) players: Players,
 
+    /// How the game was set up, with the seed that was used filled in.
+    pub(super) config: GameConfig,
+
+    /// The player who took the first turn of the game.
+    pub(super) starting_player: PlayerId,
+
+    /// Which players have kept their opening hands or are about to take a
+    /// mulligan, before the first turn begins.
+    pub(super) mulligans: Mulligans,
+
+    /// The seed that the game's random number generator started from. A game
+    /// created with the same seed that has the same actions taken in it will
+    /// always play out the same way.
+    seed: u64,
+
+    /// The source of every random decision made in the game, like shuffling
+    /// libraries and flipping coins.
+    pub(super) rng: ChaCha8Rng,
+
     /// The current turn. Starts at 0 before the first untap step, then proceeds
     /// at the end of each round of turns.
-    turn_number: u64,
+    pub(super) turn_number: u64,
 
     /// For this round of priority passing, tracks which players have had a
     /// chance to take an action and have already passed priority.
     ///
     /// When all players have passed priority, the step and/or turn advances.
-    players_that_have_passed: HashSet<PlayerId>,
+    pub(super) players_that_have_passed: BTreeSet<PlayerId>,
 
     /// The Active Player (AP) is the player whose turn it is. All other players
     /// are Non-Active Players (NAP).
@@ -46,18 +81,47 @@ pub struct Game {
     /// The current step in the game.
     pub(super) step: Step,
 
+    /// Set when players received priority during this cleanup step, which
+    /// means that another cleanup step will follow it. See rule 514.3a.
+    pub(super) repeat_cleanup_step: bool,
+
     /// The current state of the game; what the game dictates must happen next
     /// to proceed.
     pub(super) state: GameState,
 
     /// Tracks all zones in the game, used as an index into `world`, which
     /// contains this information as well on each entity.
-    zones: HashMap<ZoneId, Zone>,
+    pub(super) zones: HashMap<ZoneId, Zone>,
+
+    /// Triggered abilities that haven't been placed on the stack yet. These
+    /// abilities will be placed on the stack in APNAP order the next time a
+    /// player would receive priority.
+    pub(super) pending_triggers: Vec<TriggeredAbility>,
+
+    /// Events that are waiting for a player to choose which replacement
+    /// effect to apply to them. See rule 616.1.
+    pub(super) pending_replacements: VecDeque<PendingReplacement>,
 
-    // TODO: Triggered abilities that haven't been placed on the stack yet.
-    // These abilities will be placed on an order in APNAP order, with each
-    // player choosing how to order the individual triggers.
-    _pending_triggers: (),
+    /// The state that the game will return to once all pending replacement
+    /// effect choices have been made.
+    pub(super) state_before_replacements: Option<GameState>,
+
+    /// Replacement effects that have already been applied to the event that is
+    /// currently being replaced. Events that happen while those replacements
+    /// are carried out can't be replaced by them again. See rule 614.5.
+    pub(super) replacements_applying: Vec<Entity>,
+
+    /// Snapshots of the game from the moment each spell that is still being
+    /// cast was proposed, used to reverse the casting if it's canceled. See
+    /// rule 726.
+    pub(super) spell_proposals: Vec<(Entity, GameSnapshot)>,
+
+    /// Every action taken in the game so far, along with the game as it was
+    /// set up. Starts recording when the first action is taken.
+    pub(super) action_log: Option<ActionLog>,
+
+    /// Events that have happened since consumers last drained them.
+    pub(super) events: Vec<GameEvent>,
 }
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -83,11 +147,34 @@ pub enum GameOutcome {
 }
 
 impl Game {
-    pub fn new() -> Self {
-        let object_db = ObjectDb::load();
+    /// Set up a game and start it, following the procedure in rule 103.
+    ///
+    /// Fails if the config has no players, or if a deck contains a card that
+    /// isn't in the object database.
+    pub fn new(config: GameConfig) -> Result<Self, StartError> {
+        Self::with_object_db(config, ObjectDb::load())
+    }
+
+    /// Set up a game like [`new`](Self::new), with cards from the given
+    /// database instead of the cards built into the engine.
+    pub fn with_object_db(config: GameConfig, object_db: ObjectDb) -> Result<Self, StartError> {
+        let mut game = Self::blank(config, object_db)?;
+        super::start::start_game(&mut game)?;
+        Ok(game)
+    }
+
+    /// Create a game with the players from the config and nothing else, which
+    /// hasn't started yet.
+    pub(super) fn blank(mut config: GameConfig, object_db: ObjectDb) -> Result<Self, StartError> {
+        if config.players.is_empty() {
+            return Err(StartError::NoPlayers);
+        }
+
+        let seed = *config.seed.get_or_insert_with(rand::random);
         let world = World::new();
 
-        let players = Players::new(2);
+        let names = config.players.iter().map(|player| player.name.clone());
+        let players = Players::new(names, config.starting_life);
 
         let mut zones = maplit::hashmap! {
             ZoneId::Stack => Zone::new(),
@@ -104,42 +191,97 @@ impl Game {
 
         let player1_id = players.iter().next().unwrap().id;
 
-        Self {
+        Ok(Self {
             object_db,
             world,
             next_timestamp: 0,
             players,
-            turn_number: 1,
-            players_that_have_passed: HashSet::new(),
+            config,
+            starting_player: player1_id,
+            mulligans: Mulligans::default(),
+            seed,
+            rng: ChaCha8Rng::seed_from_u64(seed),
+            turn_number: 0,
+            players_that_have_passed: BTreeSet::new(),
             active_player: player1_id,
-            step: Step::Upkeep,
+            step: Step::Untap,
+            repeat_cleanup_step: false,
             state: GameState::Player {
                 player: player1_id,
                 action: PlayerActionCategory::Priority,
             },
             zones,
-            _pending_triggers: (),
-        }
+            pending_triggers: Vec::new(),
+            pending_replacements: VecDeque::new(),
+            state_before_replacements: None,
+            replacements_applying: Vec::new(),
+            spell_proposals: Vec::new(),
+            action_log: None,
+            events: Vec::new(),
+        })
     }
 
-    pub fn do_action(&mut self, player: PlayerId, action: PlayerAction) {
+    /// Attempt to perform an action on behalf of a player. If the action is
+    /// not legal, the game is left as it was and the reason is returned.
+    pub fn do_action(&mut self, player: PlayerId, action: PlayerAction) -> Result<(), ActionError> {
         log::debug!("Player {:?} attempting action {:?}", player, action);
 
-        match action {
-            PlayerAction::Concede => self.player_loses(player),
+        super::replay::start_recording(self);
+        let logged_action = action.clone();
+        let events_before = self.events.len();
+
+        if matches!(self.state, GameState::Complete(_)) {
+            return Err(ActionError::GameOver);
+        }
+
+        if self.players.get(player).is_none() {
+            return Err(ActionError::NoSuchPlayer { player });
+        }
+
+        let result = match action {
+            PlayerAction::Concede => {
+                self.player_loses(player);
+                super::multiplayer::continue_without(self, player);
+                Ok(())
+            }
             PlayerAction::PassPriority => self.pass_priority(player),
 
+            PlayerAction::ChooseStartingPlayer {
+                player: starting_player,
+            } => super::start::choose_starting_player(self, player, starting_player),
+            PlayerAction::Mulligan => super::start::mulligan(self, player),
+            PlayerAction::KeepHand { bottom } => super::start::keep_hand(self, player, &bottom),
+            PlayerAction::UseMulliganAbility { card } => {
+                super::start::use_mulligan_ability(self, player, card)
+            }
+
+            // 508.1. [...] If at any point during the declaration of
+            //        attackers, the active player is unable to comply with any
+            //        of the steps listed below, the declaration is illegal; the
+            //        game returns to the moment before the declaration.
             PlayerAction::ChooseAttackers { attackers } => {
-                super::combat::choose_attackers(self, player, &attackers)
+                super::snapshot::transaction(self, |game| {
+                    super::combat::choose_attackers(game, player, &attackers)
+                })
             }
 
+            // 509.1. [...] If at any point during the declaration of
+            //        blockers, the defending player is unable to comply with
+            //        any of the steps listed below, the declaration is illegal;
+            //        the game returns to the moment before the declaration.
             PlayerAction::ChooseBlockers { blockers } => {
-                super::combat::choose_blockers(self, player, &blockers)
+                super::snapshot::transaction(self, |game| {
+                    super::combat::choose_blockers(game, player, &blockers)
+                })
+            }
+
+            PlayerAction::DiscardToHandSize { cards } => {
+                super::cleanup::discard_to_hand_size(self, player, &cards)
             }
 
             PlayerAction::PlayLand { card } => self.play_land(player, card),
-            PlayerAction::StartCastingSpell { spell } => {
-                super::casting::start_casting_spell(self, player, spell)
+            PlayerAction::StartCastingSpell { spell, x } => {
+                super::casting::start_casting_spell(self, player, spell, x)
             }
             PlayerAction::FinishCastingSpell { spell } => {
                 super::casting::finish_casting_spell(self, player, spell)
@@ -150,7 +292,31 @@ impl Game {
             PlayerAction::PayIncompleteSpellMana { spell, mana } => {
                 super::casting::pay_spell_mana(self, player, spell, mana)
             }
+            PlayerAction::PayIncompleteSpellLife { spell } => {
+                super::casting::pay_spell_life(self, player, spell)
+            }
+            PlayerAction::AutoPaySpell { spell } => {
+                super::casting::auto_pay_spell(self, player, spell)
+            }
+
+            PlayerAction::ChooseReplacementEffect { effect } => {
+                super::replacement::choose_replacement_effect(self, player, effect)
+            }
+        };
+
+        match &result {
+            Ok(()) => super::replay::record(self, player, logged_action),
+            Err(err) => {
+                log::warn!("Player {:?} could not take action: {}", player, err);
+
+                // The action was reversed, so nothing it did happened.
+                self.events.truncate(events_before);
+            }
         }
+
+        super::replacement::request_choices(self);
+
+        result
     }
 
     pub fn create_card(&mut self, id: CardId, zone_id: ZoneId, owner: PlayerId) -> Option<Entity> {
@@ -173,6 +339,8 @@ impl Game {
             supertypes: descriptor.supertypes.clone(),
             subtypes: descriptor.subtypes.clone(),
             mana_cost: descriptor.mana_cost.clone(),
+            chapter_abilities: descriptor.chapter_abilities.clone(),
+            mulligan_abilities: descriptor.mulligan_abilities.clone(),
             pt: descriptor.pt,
             zone: zone_id,
             owner,
@@ -192,27 +360,132 @@ impl Game {
         let entity = self.world.spawn(builder.build());
         zone.add(entity);
 
+        if zone_id == ZoneId::Battlefield {
+            super::sagas::enter_battlefield(self, entity);
+        }
+
         Some(entity)
     }
 
-    pub fn move_object_to_zone(&mut self, object_id: Entity, zone_id: ZoneId) -> Option<()> {
+    /// Create every card in a deck's main deck in a player's library, on top
+    /// of anything already there. The library isn't shuffled. If any card
+    /// isn't in the object database, no cards are created.
+    pub fn load_deck(&mut self, player: PlayerId, deck: &Deck) -> Result<Vec<Entity>, StartError> {
+        let cards = deck.main_cards();
+        if let Some(&card) = cards
+            .iter()
+            .find(|&&card| self.object_db.card(card).is_none())
+        {
+            return Err(StartError::UnknownCard { player, card });
+        }
+
+        cards
+            .into_iter()
+            .map(|card| {
+                self.create_card(card, ZoneId::Library(player), player)
+                    .ok_or(StartError::UnknownCard { player, card })
+            })
+            .collect()
+    }
+
+    /// 106.4. When an effect instructs a player to add mana, that mana goes into
+    ///        a special holding area called a mana pool. [...]
+    pub fn add_mana(&mut self, player: PlayerId, mana: impl Into<Mana>) -> Option<ManaId> {
+        match self.players.get_mut(player) {
+            Some(player) => Some(player.mana_pool.add(mana.into())),
+            None => {
+                log::warn!("Cannot add mana for {:?}: not a player", player);
+                None
+            }
+        }
+    }
+
+    /// Start an effect that changes what happens to a player's unspent mana
+    /// as steps and phases end. Returns the entity that holds the effect.
+    pub fn add_unspent_mana_effect(&mut self, effect: UnspentManaEffect) -> Entity {
+        self.add_effect((effect,))
+    }
+
+    /// Start an effect like [`add_unspent_mana_effect`](Self::add_unspent_mana_effect)
+    /// that ends during the cleanup step of this turn.
+    pub fn add_unspent_mana_effect_until_end_of_turn(
+        &mut self,
+        effect: UnspentManaEffect,
+    ) -> Entity {
+        self.add_effect((effect, UntilEotEffect))
+    }
+
+    /// Start an effect that isn't part of an object, giving it the next
+    /// timestamp so that it applies after the effects that began before it.
+    pub(crate) fn add_effect(&mut self, components: impl DynamicBundle) -> Entity {
+        let timestamp = Timestamp(self.timestamp());
+        let entity = self.world.spawn(components);
+        self.world.insert_one(entity, timestamp).unwrap();
+        entity
+    }
+
+    fn timestamp(&mut self) -> u64 {
+        let timestamp = self.next_timestamp;
+        self.next_timestamp += 1;
+        timestamp
+    }
+
+    /// Move an object to a zone, giving replacement effects the chance to
+    /// modify the move. Returns whether the object is in that zone afterward,
+    /// which it isn't if the move was replaced or is waiting for a player to
+    /// choose a replacement effect.
+    pub fn move_object_to_zone(&mut self, object_id: Entity, zone_id: ZoneId) -> bool {
         if !self.zones.contains_key(&zone_id) {
             log::warn!(
                 "Cannot move object {:?} to zone {:?}: the zone does not exist",
                 object_id,
                 zone_id,
             );
-            return None;
+            return false;
         }
 
-        let mut object = self.world.get_mut::<Object>(object_id).ok()?;
-        let old_zone_id = object.zone;
+        let old_zone_id = match self.world.get::<Object>(object_id) {
+            Ok(object) => object.zone,
+            Err(_) => return false,
+        };
         if zone_id == old_zone_id {
             log::warn!(
                 "Cannot move object {:?} to zone {:?}: it is already in that zone",
                 object_id,
                 zone_id,
             );
+            return true;
+        }
+
+        super::replacement::perform(
+            self,
+            ReplaceableEvent::ZoneChange {
+                object: object_id,
+                to: zone_id,
+                tapped: false,
+            },
+        );
+
+        self.world
+            .get::<Object>(object_id)
+            .is_ok_and(|object| object.zone == zone_id)
+    }
+
+    /// Move an object to a zone after any replacement effects have been
+    /// applied to the move.
+    pub(super) fn apply_zone_change(
+        &mut self,
+        object_id: Entity,
+        zone_id: ZoneId,
+        tapped: bool,
+    ) -> Option<()> {
+        if !self.zones.contains_key(&zone_id) {
+            return None;
+        }
+
+        let mut object = self.world.get_mut::<Object>(object_id).ok()?;
+        let old_zone_id = object.zone;
+        if zone_id == old_zone_id {
             return Some(());
         }
 
@@ -233,10 +506,30 @@ impl Game {
         // 110.2a If an effect instructs a player to put an object onto the
         //        battlefield, that object enters the battlefield under that
         //        player’s control unless the effect states otherwise.
-        object.controller = Some(object.owner);
+        object.controller = if zone_id == ZoneId::Battlefield {
+            Some(object.controller_on_battlefield())
+        } else {
+            Some(object.owner)
+        };
 
         drop(object);
 
+        self.emit(GameEvent::ZoneChange {
+            object: object_id,
+            from: old_zone_id,
+            to: zone_id,
+        });
+
+        // 122.2. Counters on an object are not retained if that object moves
+        //        from one zone to another. The counters are not “removed”; they
+        //        simply cease to exist. See rule 400.7.
+        let _ = self.world.remove_one::<Counters>(object_id);
+
+        // 400.7. An object that moves from one zone to another becomes a new
+        //        object with no memory of or relation to its previous
+        //        existence.
+        let _ = self.world.remove_one::<Revealed>(object_id);
+
         // 110.1. A permanent is a card or token on the battlefield. A permanent
         //        remains on the battlefield indefinitely. A card or token
         //        becomes a permanent as it enters the battlefield and it stops
@@ -244,8 +537,10 @@ impl Game {
         //        or rule.
         if zone_id == ZoneId::Battlefield {
             self.world
-                .insert_one(object_id, Permanent { tapped: false })
+                .insert_one(object_id, Permanent { tapped })
                 .unwrap();
+
+            super::sagas::enter_battlefield(self, object_id);
         } else if old_zone_id == ZoneId::Battlefield {
             let _ = self.world.remove_one::<Permanent>(object_id);
         }
@@ -253,6 +548,100 @@ impl Game {
         Some(())
     }
 
+    /// Change a player's life total, gaining life if `change` is positive and
+    /// losing life if it's negative.
+    pub(super) fn change_life(&mut self, player: PlayerId, change: i64) {
+        let life = match self.players.get_mut(player) {
+            Some(player) => {
+                player.life += change;
+                player.life
+            }
+            None => return,
+        };
+
+        if change != 0 {
+            self.emit(GameEvent::LifeChanged {
+                player,
+                change,
+                life,
+            });
+        }
+    }
+
+    pub(super) fn emit(&mut self, event: GameEvent) {
+        log::trace!("Event: {:?}", event);
+        self.events.push(event);
+    }
+
+    /// Take every event that has happened since events were last drained, in
+    /// the order they happened.
+    ///
+    /// Events include hidden information, like which object each player drew,
+    /// so they shouldn't be shown to players who can't see it. Use
+    /// [`view`](Self::view) for what a player is allowed to know.
+    pub fn drain_events(&mut self) -> Vec<GameEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    /// Capture everything about the game that can change, so that the game can
+    /// later be returned to this moment with [`restore`](Self::restore).
+    pub fn snapshot(&self) -> GameSnapshot {
+        super::snapshot::snapshot(self)
+    }
+
+    /// Return the game to the moment that a snapshot was taken.
+    pub fn restore(&mut self, snapshot: GameSnapshot) {
+        super::snapshot::restore(self, snapshot)
+    }
+
+    /// Save the entire game as versioned JSON, which can be loaded with
+    /// [`load`](Self::load) to continue the game from this moment.
+    pub fn save(&self) -> serde_json::Result<String> {
+        super::save::save(self)
+    }
+
+    /// Load a game saved with [`save`](Self::save).
+    pub fn load(source: &str) -> Result<Self, LoadError> {
+        super::save::load(source, ObjectDb::load())
+    }
+
+    /// Load a game saved with [`save`](Self::save) that was created with
+    /// [`with_object_db`](Self::with_object_db), using the same cards.
+    pub fn load_with_object_db(source: &str, object_db: ObjectDb) -> Result<Self, LoadError> {
+        super::save::load(source, object_db)
+    }
+
+    /// The setup of the game and every action that has been successfully
+    /// taken in it, or `None` if no actions have been taken yet.
+    ///
+    /// Changes made to the game other than through [`do_action`](Self::do_action)
+    /// once the first action has been taken are not recorded.
+    pub fn action_log(&self) -> Option<&ActionLog> {
+        self.action_log.as_ref()
+    }
+
+    /// Record the current state of the game, which a replay of the game's
+    /// action log can be checked against.
+    pub fn checkpoint(&self) -> Checkpoint {
+        super::replay::checkpoint(self)
+    }
+
+    /// Play a game back from its action log, returning the first point where
+    /// the replay doesn't match one of the checkpoints.
+    pub fn replay(log: &ActionLog, checkpoints: &[Checkpoint]) -> Result<Self, Divergence> {
+        super::replay::replay(log, checkpoints, ObjectDb::load())
+    }
+
+    /// Play back a game that was created with
+    /// [`with_object_db`](Self::with_object_db), using the same cards.
+    pub fn replay_with_object_db(
+        log: &ActionLog,
+        checkpoints: &[Checkpoint],
+        object_db: ObjectDb,
+    ) -> Result<Self, Divergence> {
+        super::replay::replay(log, checkpoints, object_db)
+    }
+
     pub fn object_db(&self) -> &ObjectDb {
         &self.object_db
     }
@@ -267,14 +656,74 @@ impl Game {
         &mut self.world
     }
 
-    /// Temporary function: used for game setup in UI
-    pub fn shuffle_libraries(&mut self) {
-        for player in &self.players {
-            let library = self.zones.get_mut(&ZoneId::Library(player.id)).unwrap();
-            library.shuffle();
+    /// 701.20a To shuffle a library or a face-down pile of cards is to
+    ///         randomize the cards within it so that no player knows their
+    ///         order.
+    pub fn shuffle_library(&mut self, player: PlayerId) {
+        if let Some(library) = self.zones.get_mut(&ZoneId::Library(player)) {
+            library.shuffle(&mut self.rng);
         }
     }
 
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// How the game was set up.
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Returns representative actions that the given player can currently
+    /// take. Attack and block declarations, choices of cards and values of X
+    /// are a sample of the legal ones rather than all of them. See
+    /// [`legal_attackers`](Self::legal_attackers),
+    /// [`legal_blockers`](Self::legal_blockers) and
+    /// [`cards_to_discard`](Self::cards_to_discard) for what can be part of
+    /// each declaration or choice.
+    pub fn legal_actions(&self, player: PlayerId) -> Vec<PlayerAction> {
+        super::legal_actions::legal_actions(self, player)
+    }
+
+    /// Returns the creatures that the given player can declare as attackers.
+    /// Any subset of them is a legal declaration, with each one attacking any
+    /// of the [`legal_attack_targets`](Self::legal_attack_targets).
+    pub fn legal_attackers(&self, player: PlayerId) -> Vec<Entity> {
+        super::legal_actions::legal_attackers(self, player)
+    }
+
+    /// Returns the players and planeswalkers that the given player's creatures
+    /// can attack.
+    pub fn legal_attack_targets(&self, player: PlayerId) -> Vec<Target> {
+        super::legal_actions::legal_attack_targets(self, player)
+    }
+
+    /// Returns the creatures that the given player can declare as blockers.
+    /// Any subset of them is a legal declaration, with each one blocking any
+    /// of the [`blockable_attackers`](Self::blockable_attackers).
+    pub fn legal_blockers(&self, player: PlayerId) -> Vec<Entity> {
+        super::legal_actions::legal_blockers(self, player)
+    }
+
+    /// Returns the attacking creatures that the given player can block, which
+    /// are the ones attacking them or their planeswalkers.
+    pub fn blockable_attackers(&self, player: PlayerId) -> Vec<Entity> {
+        super::legal_actions::blockable_attackers(self, player)
+    }
+
+    /// Returns how many cards the given player has to discard during their
+    /// cleanup step. Any that many cards from their hand are a legal discard.
+    pub fn cards_to_discard(&self, player: PlayerId) -> usize {
+        super::cleanup::cards_to_discard(self, player)
+    }
+
+    /// Returns how many cards the given player puts on the bottom of their
+    /// library if they keep their current opening hand. Any that many cards
+    /// from their hand can be chosen.
+    pub fn cards_to_bottom(&self, player: PlayerId) -> usize {
+        super::start::cards_to_bottom(self, player)
+    }
+
     /// Resolve a given query to compute a property of the game state, like a
     /// property of a game object.
     pub fn query<Q: Query>(&self, query_object: Q) -> Q::Output {
@@ -293,6 +742,47 @@ impl Game {
         self.zones.get(&id)
     }
 
+    /// Project the game as seen by a player or a spectator, leaving out
+    /// everything that they aren't allowed to see.
+    pub fn view(&self, viewer: Viewer) -> PlayerView {
+        super::view::view(self, viewer)
+    }
+
+    /// Whether a player or spectator can see an object where it is now, by
+    /// the same rules as [`view`](Self::view).
+    pub fn can_see(&self, viewer: Viewer, entity: Entity) -> bool {
+        match self.world.get::<Object>(entity) {
+            Ok(object) => super::view::can_see(self, viewer, object.zone, entity),
+            Err(_) => false,
+        }
+    }
+
+    /// Reveal a card to every player and spectator until it changes zones.
+    pub fn reveal_to_all(&mut self, card: Entity) {
+        let _ = self.world.insert_one(card, Revealed::ToAll);
+    }
+
+    /// Reveal a card to a single player until it changes zones, like when an
+    /// effect lets a player look at a card.
+    pub fn reveal_to(&mut self, card: Entity, player: PlayerId) {
+        let revealed = match self.world.get::<Revealed>(card).as_deref() {
+            Ok(Revealed::ToAll) => return,
+            Ok(Revealed::ToPlayers { players }) => {
+                let mut players = players.clone();
+                if !players.contains(&player) {
+                    players.push(player);
+                }
+
+                Revealed::ToPlayers { players }
+            }
+            Err(_) => Revealed::ToPlayers {
+                players: vec![player],
+            },
+        };
+
+        let _ = self.world.insert_one(card, revealed);
+    }
+
     /// Returns all players in turn order.
     pub fn players(&self) -> &Players {
         &self.players
@@ -315,15 +805,36 @@ impl Game {
         }
     }
 
-    /// Check state-based actions and then give a player priority as long as the
-    /// game hasn't ended as a result.
+    /// Check state-based actions, put any triggered abilities on the stack, and
+    /// then give a player priority as long as the game hasn't ended as a
+    /// result.
     pub(super) fn give_priority(&mut self, player: PlayerId) {
-        self.apply_state_based_actions();
+        loop {
+            self.apply_state_based_actions();
 
-        if matches!(self.state, GameState::Complete(_)) {
-            return;
+            if matches!(self.state, GameState::Complete(_)) {
+                return;
+            }
+
+            if !self.pending_replacements.is_empty() {
+                break;
+            }
+
+            if !super::triggers::put_pending_triggers_on_stack(self) {
+                break;
+            }
         }
 
+        // 800.4j [...] If the active player would receive priority, instead
+        //        the next player in turn order receives priority, or the top
+        //        object on the stack resolves, or the step ends, whichever is
+        //        appropriate.
+        let player = if self.players.is_in_game(player) {
+            player
+        } else {
+            self.players.player_after(player)
+        };
+
         self.state = GameState::Player {
             player,
             action: PlayerActionCategory::Priority,
@@ -338,12 +849,6 @@ impl Game {
         self.give_priority(player);
     }
 
-    pub(super) fn timestamp(&mut self) -> u64 {
-        let timestamp = self.next_timestamp;
-        self.next_timestamp += 1;
-        timestamp
-    }
-
     /// 704. State-Based Actions (https://mtg.gamepedia.com/State-based_action)
     ///
     /// 704.3. Whenever a player would get priority (see rule 117, “Timing and
@@ -361,23 +866,42 @@ impl Game {
     ///        of the step’s first check and no triggered abilities are waiting
     ///        to be put on the stack, then no player gets priority and the step
     ///        ends.
-    fn apply_state_based_actions(&mut self) {
+    ///
+    /// Returns whether any state-based actions were performed.
+    pub(super) fn apply_state_based_actions(&mut self) -> bool {
+        let mut any_performed = false;
+
         loop {
             if !super::state_based_actions::apply(self) {
                 break;
             }
+
+            any_performed = true;
+
+            // Events waiting on a choice of replacement effect haven't happened
+            // yet, so checking again would only repeat them. State-based
+            // actions are checked again once the choices have been made.
+            if !self.pending_replacements.is_empty() {
+                break;
+            }
         }
+
+        any_performed
     }
 
-    fn pass_priority(&mut self, player: PlayerId) {
+    fn pass_priority(&mut self, player: PlayerId) -> Result<(), ActionError> {
         if self.priority_player() != Some(player) {
-            log::warn!(
-                "Player {:?} tried to pass priority but is not the priority player",
-                player
-            );
-            return;
+            return Err(ActionError::NotPriorityPlayer);
         }
 
+        self.priority_passed(player);
+
+        Ok(())
+    }
+
+    /// Move on from a player that had priority and either passed it or left
+    /// the game.
+    pub(super) fn priority_passed(&mut self, player: PlayerId) {
         self.players_that_have_passed.insert(player);
 
         // 117.4. If all players pass in succession (that is, if all players
@@ -406,12 +930,21 @@ impl Game {
         }
     }
 
-    fn end_current_step(&mut self) {
+    pub(super) fn end_current_step(&mut self) {
         log::debug!("Ending current step");
 
         let stack = &self.zones[&ZoneId::Stack];
         assert!(stack.is_empty());
 
+        if self.step == Step::EndCombat {
+            super::combat::leave_end_combat(self);
+        }
+
+        // 500.4. When a step or phase ends, any unused mana left in a player’s
+        //        mana pool empties. This turn-based action doesn’t use the
+        //        stack.
+        super::mana_pools::empty_mana_pools(self);
+
         if let Some(next_step) = self.next_step() {
             // Advancing to the next step within the same turn.
             self.enter_step(next_step);
@@ -428,7 +961,8 @@ impl Game {
         assert!(stack.is_empty());
 
         let next_player = self.players.player_after(self.active_player);
-        let is_new_turn_cycle = next_player.to_u32() == 0;
+        let is_new_turn_cycle =
+            self.turn_order_position(next_player) <= self.turn_order_position(self.active_player);
 
         {
             for player in &mut self.players {
@@ -436,18 +970,34 @@ impl Game {
             }
         }
 
-        self.active_player = next_player;
-        self.enter_step(Step::Untap);
-
+        // The turn number changes first, so that the events of the new
+        // turn's first steps carry it.
         if is_new_turn_cycle {
             self.turn_number += 1;
         }
+
+        self.active_player = next_player;
+        self.enter_step(Step::Untap);
     }
 
-    fn enter_step(&mut self, step: Step) {
+    /// How many turns after the starting player's the given player takes their
+    /// turn.
+    pub(super) fn turn_order_position(&self, player: PlayerId) -> usize {
+        let num_players = self.players.len();
+        let position = |player: PlayerId| player.to_u32() as usize;
+
+        (position(player) + num_players - position(self.starting_player)) % num_players
+    }
+
+    pub(super) fn enter_step(&mut self, step: Step) {
         log::debug!("Entering step {:?}", step);
 
         self.step = step;
+        self.emit(GameEvent::StepEntered {
+            step,
+            active_player: self.active_player,
+            turn_number: self.turn_number,
+        });
 
         match step {
             // 502. Untap Step
@@ -519,8 +1069,9 @@ impl Game {
                 //        main phase, the active player puts a lore counter on
                 //        each Saga they control. (See rule 714, “Saga Cards.”)
                 //        This turn-based action doesn’t use the stack.
-                //
-                // TODO
+                if step == Step::Main1 {
+                    super::sagas::enter_precombat_main(self);
+                }
 
                 // 505.5. Third, the active player gets priority. (See rule 117,
                 //        “Timing and Priority.”)
@@ -542,58 +1093,7 @@ impl Game {
             }
 
             // 514. Cleanup Step
-            Step::Cleanup => {
-                // 514.1. First, if the active player’s hand contains more cards
-                //        than their maximum hand size (normally seven), they
-                //        discard enough cards to reduce their hand size to that
-                //        number. This turn-based action doesn’t use the stack.
-                //
-                // TODO
-
-                // 514.2. Second, the following actions happen simultaneously:
-                //        all damage marked on permanents (including phased-out
-                //        permanents) is removed and all “until end of turn” and
-                //        “this turn” effects end. This turn-based action
-                //        doesn’t use the stack.
-                let mut damage_to_remove = Vec::new();
-                for (entity, _damage) in self.world.query_mut::<(&Damage,)>() {
-                    damage_to_remove.push(entity);
-                }
-
-                for entity in damage_to_remove {
-                    let _ = self.world.remove_one::<Damage>(entity);
-                }
-
-                let mut to_despawn = Vec::new();
-                for (entity, _effect) in self.world.query_mut::<(&UntilEotEffect,)>() {
-                    to_despawn.push(entity);
-                }
-
-                for entity in to_despawn {
-                    let _ = self.world.despawn(entity);
-                }
-
-                // 514.3. Normally, no player receives priority during the
-                //        cleanup step, so no spells can be cast and no
-                //        abilities can be activated. However, this rule is
-                //        subject to the following exception:
-                // 514.3a At this point, the game checks to see if any
-                //        state-based actions would be performed and/or any
-                //        triggered abilities are waiting to be put onto the
-                //        stack (including those that trigger “at the beginning
-                //        of the next cleanup step”). If so, those state-based
-                //        actions are performed, then those triggered abilities
-                //        are put on the stack, then the active player gets
-                //        priority. Players may cast spells and activate
-                //        abilities. Once the stack is empty and all players
-                //        pass in succession, another cleanup step begins.
-                self.apply_state_based_actions();
-
-                // TODO: Put stuff onto the stack, give priority if there was
-                // anything.
-
-                self.end_current_step();
-            }
+            Step::Cleanup => super::cleanup::enter_cleanup(self),
         }
     }
 
@@ -604,6 +1104,24 @@ impl Game {
             None => return,
         };
 
+        let ability = self
+            .world
+            .get::<TriggeredAbility>(top)
+            .ok()
+            .map(|ability| (*ability).clone());
+
+        if let Some(ability) = ability {
+            // 113.7a Once activated or triggered, an ability exists on the
+            //        stack independently of its source. An ability that leaves
+            //        the stack ceases to exist.
+            self.zones.get_mut(&ZoneId::Stack).unwrap().remove(top);
+            let _ = self.world.despawn(top);
+
+            super::effects::apply(self, ability.controller, &ability.effects);
+            self.start_priority_round(self.active_player);
+            return;
+        }
+
         let object = match self.world.get::<Object>(top) {
             Ok(object) => object,
             Err(_) => {
@@ -617,7 +1135,7 @@ impl Game {
         //        of a permanent spell, or a mutating creature spell). The spell
         //        card becomes a permanent and is put onto the battlefield under
         //        the control of the spell’s controller.
-        if object.types.contains(&CardType::Creature) {
+        if object.types.iter().any(CardType::is_permanent_type) {
             drop(object);
             self.move_object_to_zone(top, ZoneId::Battlefield);
         } else {
@@ -643,20 +1161,39 @@ impl Game {
         }
 
         let mut players_left = Vec::new();
+        let mut new_losers = Vec::new();
         for player in &mut self.players {
             if losers.contains(&player.id) {
+                if !player.has_lost {
+                    new_losers.push(player.id);
+                }
+
                 player.has_lost = true;
-            } else {
+            } else if !player.has_lost {
                 players_left.push(player.id);
             }
         }
 
-        if players_left.len() == 0 {
+        for &player in &new_losers {
+            self.emit(GameEvent::PlayerLost { player });
+        }
+
+        // 104.2a A player still in the game wins the game if that player’s
+        //        opponents have all left the game.
+        if players_left.is_empty() {
             self.state = GameState::Complete(GameOutcome::Draw);
+            return;
         } else if players_left.len() == 1 {
             self.state = GameState::Complete(GameOutcome::Win {
                 winner: players_left[0],
             });
+            return;
+        }
+
+        // 800.4. Unlike two-player games, multiplayer games can continue after
+        //        one or more players have left the game.
+        for player in new_losers {
+            super::multiplayer::leave_game(self, player);
         }
     }
 
@@ -670,7 +1207,13 @@ impl Game {
         match self.step {
             Step::Untap => Some(Step::Upkeep),
             Step::Upkeep => {
-                if self.turn_number == 1 && self.active_player.to_u32() == 0 {
+                // 103.7a In a two-player game, the player who plays first
+                //        skips the draw step (see rule 504, “Draw Step”) of
+                //        their first turn.
+                if self.turn_number == 1
+                    && self.active_player == self.starting_player
+                    && self.config.starting_player_skips_draw
+                {
                     return Some(Step::Main1);
                 }
 
@@ -685,15 +1228,27 @@ impl Game {
             Step::EndCombat => Some(Step::Main2),
             Step::Main2 => Some(Step::End),
             Step::End => Some(Step::Cleanup),
-            Step::Cleanup => None,
+            Step::Cleanup => {
+                if self.repeat_cleanup_step {
+                    return Some(Step::Cleanup);
+                }
+
+                None
+            }
         }
     }
 
-    fn draw_card(&mut self, player: PlayerId) {
+    pub(super) fn draw_card(&mut self, player: PlayerId) {
+        super::replacement::perform(self, ReplaceableEvent::Draw { player });
+    }
+
+    /// Draw a card after any replacement effects have been applied to the draw.
+    pub(super) fn apply_draw(&mut self, player: PlayerId) {
         let library = self.zones.get(&ZoneId::Library(player)).unwrap();
         match library.members().last() {
             Some(&card) => {
-                self.move_object_to_zone(card, ZoneId::Hand(player));
+                self.apply_zone_change(card, ZoneId::Hand(player), false);
+                self.emit(GameEvent::Draw { player, card });
             }
             None => {
                 let player = self.players.get_mut(player).unwrap();
@@ -702,7 +1257,64 @@ impl Game {
         }
     }
 
-    fn play_land(&mut self, player: PlayerId, land: Entity) {
+    /// 701.8a To discard a card, move it from its owner’s hand to that
+    ///        player’s graveyard.
+    pub(super) fn discard(&mut self, card: Entity) {
+        let owner = match self.world.get::<Object>(card) {
+            Ok(object) => object.owner,
+            Err(_) => return,
+        };
+
+        self.move_object_to_zone(card, ZoneId::Graveyard(owner));
+    }
+
+    /// Discard cards chosen at random from a player's hand.
+    pub(super) fn discard_at_random(&mut self, player: PlayerId, count: usize) {
+        for _ in 0..count {
+            let hand = self.zones[&ZoneId::Hand(player)].members();
+            if hand.is_empty() {
+                return;
+            }
+
+            let card = hand[self.rng.gen_range(0..hand.len())];
+            self.discard(card);
+        }
+    }
+
+    /// 705.1. To flip a coin for an object that cares whether a player wins
+    ///        or loses the flip, the affected player flips the coin and calls
+    ///        heads or tails. If the call matches the result, that player wins
+    ///        the flip. Otherwise, that player loses the flip.
+    ///
+    /// Returns whether the player won the flip.
+    pub(super) fn flip_coin(&mut self, player: PlayerId) -> bool {
+        let won = self.rng.gen_bool(0.5);
+        log::debug!("Player {:?} flipped a coin and won: {}", player, won);
+        won
+    }
+
+    /// 103.1. At the start of a game, the players determine which one of them
+    ///        will choose who takes the first turn. In the first game of a
+    ///        match (including a single-game match), the players may use any
+    ///        mutually agreeable method (flipping a coin, rolling dice, etc.)
+    ///        to do so.
+    pub(super) fn random_player(&mut self) -> PlayerId {
+        let players: Vec<_> = self.players.in_game().map(|player| player.id).collect();
+        players[self.rng.gen_range(0..players.len())]
+    }
+
+    /// 701.17a To sacrifice a permanent, its controller moves it from the
+    ///         battlefield directly to its owner’s graveyard.
+    pub(super) fn sacrifice(&mut self, permanent: Entity) {
+        let owner = match self.world.get::<Object>(permanent) {
+            Ok(object) => object.owner,
+            Err(_) => return,
+        };
+
+        self.move_object_to_zone(permanent, ZoneId::Graveyard(owner));
+    }
+
+    fn play_land(&mut self, player: PlayerId, land: Entity) -> Result<(), ActionError> {
         // 116.2a Playing a land is a special action. To play a land, a player
         //        puts that land onto the battlefield from the zone it was in
         //        (usually that player’s hand). By default, a player can take
@@ -710,45 +1322,53 @@ impl Game {
         //        take this action any time they have priority and the stack is
         //        empty during a main phase of their turn. See rule 305,
         //        “Lands.”
+        if self.active_player != player {
+            return Err(ActionError::NotActivePlayer);
+        }
 
-        fn inner(game: &mut Game, player: PlayerId, land: Entity) -> Result<(), String> {
-            if game.active_player != player {
-                return Err("it is not their turn".to_owned());
-            }
+        if self.step != Step::Main1 && self.step != Step::Main2 {
+            return Err(ActionError::NotMainPhase);
+        }
 
-            if game.step != Step::Main1 && game.step != Step::Main2 {
-                return Err("it is not the main phase".to_owned());
-            }
+        if self.priority_player() != Some(player) {
+            return Err(ActionError::NotPriorityPlayer);
+        }
 
-            if game.priority_player() != Some(player) {
-                return Err("they do not have priority".to_owned());
-            }
+        let stack = self.zone(ZoneId::Stack).unwrap();
+        if !stack.is_empty() {
+            return Err(ActionError::StackNotEmpty);
+        }
 
-            let stack = game.zone(ZoneId::Stack).unwrap();
-            if !stack.is_empty() {
-                return Err("the stack is not empty".to_owned());
-            }
+        {
+            let object = self
+                .world
+                .get::<Object>(land)
+                .map_err(|_| ActionError::NoSuchObject { object: land })?;
 
-            {
-                let mut player_object = game
-                    .players
-                    .get_mut(player)
-                    .ok_or_else(|| format!("{:?} is not a player", player))?;
+            if !object.types.contains(&CardType::Land) {
+                return Err(ActionError::NotALand { card: land });
+            }
 
-                if player_object.lands_played_this_turn > 0 {
-                    return Err("they have already played a land this turn".to_owned());
-                }
-                player_object.lands_played_this_turn += 1;
+            if object.zone != ZoneId::Hand(player) {
+                return Err(ActionError::LandNotInHand { card: land });
             }
+        }
 
-            game.move_object_to_zone(land, ZoneId::Battlefield);
+        {
+            let player_object = self
+                .players
+                .get_mut(player)
+                .ok_or(ActionError::NoSuchPlayer { player })?;
 
-            Ok(())
+            if player_object.lands_played_this_turn > 0 {
+                return Err(ActionError::LandAlreadyPlayed);
+            }
+            player_object.lands_played_this_turn += 1;
         }
 
-        if let Err(err) = inner(self, player, land) {
-            log::error!("Player {:?} cannot play land {:?}: {}", player, land, err);
-        }
+        self.move_object_to_zone(land, ZoneId::Battlefield);
+
+        Ok(())
     }
 }
 
@@ -0,0 +1,382 @@
+//! Enumeration of the actions a player can legally take, for clients and bots
+//! that need to know what their options are.
+
+use hecs::Entity;
+
+use crate::action::{AttackDeclaration, BlockDeclaration, PlayerAction, PlayerActionCategory};
+use crate::components::{Attacking, IncompleteSpell, Object, Permanent};
+use crate::cost::{ManaCostItem, PHYREXIAN_LIFE};
+use crate::player::PlayerId;
+use crate::target::Target;
+use crate::types::CardType;
+use crate::zone::ZoneId;
+
+use super::{Game, GameState, Step};
+
+/// Returns representative actions that the given player can currently take.
+/// Every kind of action the player can take is included, but choices with too
+/// many options to list are described by a sample of them.
+///
+/// Declaring attackers or blockers allows any subset of the eligible creatures,
+/// each attacking any player or planeswalker or blocking any attacker, which is
+/// too many combinations to list. Instead, those declarations are described by
+/// declaring none, each creature on its own with each choice, and every
+/// creature at once with the same choice. [`legal_attackers`],
+/// [`legal_attack_targets`], [`legal_blockers`] and [`blockable_attackers`]
+/// list what can be part of a declaration.
+///
+/// Likewise, choosing cards to discard or to put on the bottom of the library
+/// allows any that many cards from the hand. Those choices are described by
+/// each run of consecutive cards in the hand, so that every card is part of
+/// at least one. [`Game::cards_to_discard`] and [`Game::cards_to_bottom`] give
+/// how many cards are chosen.
+///
+/// The value of X for a spell can be anything up to
+/// [`MAX_GENERIC_MANA`](crate::cost::MAX_GENERIC_MANA), since mana can be
+/// added before the cost is paid. It's described by each value from 0 to the
+/// amount of mana in the player's pool.
+pub fn legal_actions(game: &Game, player: PlayerId) -> Vec<PlayerAction> {
+    let action = match &game.state {
+        GameState::Player {
+            player: acting,
+            action,
+        } if *acting == player => action,
+
+        // 104.3a A player can concede the game at any time.
+        GameState::Player { .. } => return vec![PlayerAction::Concede],
+        GameState::Complete(_) => return Vec::new(),
+    };
+
+    let mut actions = match action {
+        PlayerActionCategory::Priority => priority_actions(game, player),
+
+        PlayerActionCategory::ChooseStartingPlayer => game
+            .players
+            .in_game()
+            .map(|starting| PlayerAction::ChooseStartingPlayer {
+                player: starting.id,
+            })
+            .collect(),
+
+        PlayerActionCategory::Mulligan => mulligan_actions(game, player),
+
+        PlayerActionCategory::ChooseAttackers => declarations(
+            legal_attackers(game, player),
+            &legal_attack_targets(game, player),
+            |attacker, target| AttackDeclaration { attacker, target },
+            |attackers| PlayerAction::ChooseAttackers { attackers },
+        ),
+
+        PlayerActionCategory::ChooseBlockers => declarations(
+            legal_blockers(game, player),
+            &blockable_attackers(game, player),
+            |blocker, attacker| BlockDeclaration { blocker, attacker },
+            |blockers| PlayerAction::ChooseBlockers { blockers },
+        ),
+
+        PlayerActionCategory::DiscardToHandSize => {
+            let count = super::cleanup::cards_to_discard(game, player);
+            let hand = game.zone(ZoneId::Hand(player)).unwrap().members();
+
+            selections(hand, count)
+                .into_iter()
+                .map(|cards| PlayerAction::DiscardToHandSize { cards })
+                .collect()
+        }
+
+        PlayerActionCategory::SpellManaAbilities | PlayerActionCategory::SpellPayingCost => {
+            spell_payment_actions(game, player)
+        }
+
+        PlayerActionCategory::ChooseReplacementEffect { options } => options
+            .iter()
+            .map(|&effect| PlayerAction::ChooseReplacementEffect { effect })
+            .collect(),
+    };
+
+    actions.push(PlayerAction::Concede);
+    actions
+}
+
+/// 508.1a The active player chooses which creatures that they control, if any,
+///        will attack. The chosen creatures must be untapped, and each one must
+///        either have haste or have been controlled by the active player
+///        continuously since the turn began.
+pub fn legal_attackers(game: &Game, player: PlayerId) -> Vec<Entity> {
+    untapped_creatures(game, player)
+}
+
+/// 508.1b If the defending player controls any planeswalkers, or the game
+///        allows the active player to attack multiple other players, the
+///        active player announces which player or planeswalker each of the
+///        chosen creatures is attacking.
+pub fn legal_attack_targets(game: &Game, player: PlayerId) -> Vec<Target> {
+    let mut targets: Vec<_> = game
+        .players
+        .in_game()
+        .map(|opponent| Target::Player(opponent.id))
+        .filter(|&target| super::combat::can_attack(game, player, target))
+        .collect();
+
+    let mut planeswalkers: Vec<_> = game
+        .world
+        .query::<(&Object, &Permanent)>()
+        .iter()
+        .map(|(entity, _)| entity)
+        .filter(|&entity| super::combat::can_attack(game, player, Target::Object(entity)))
+        .collect();
+
+    // Query order isn't meaningful, so keep results stable for callers.
+    planeswalkers.sort();
+
+    targets.extend(planeswalkers.into_iter().map(Target::Object));
+    targets
+}
+
+/// 509.1a The defending player chooses which creatures they control, if any,
+///        will block. The chosen creatures must be untapped.
+pub fn legal_blockers(game: &Game, player: PlayerId) -> Vec<Entity> {
+    untapped_creatures(game, player)
+}
+
+/// 509.1a [...] For each of the chosen creatures, the defending player chooses
+///        one creature for it to block that’s attacking that player or a
+///        planeswalker that player controls.
+pub fn blockable_attackers(game: &Game, player: PlayerId) -> Vec<Entity> {
+    let mut attackers: Vec<_> = game
+        .world
+        .query::<(&Attacking,)>()
+        .iter()
+        .filter(|(_, (attacking,))| {
+            super::combat::defending_player(game, attacking.target) == Some(player)
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+
+    attackers.sort();
+    attackers
+}
+
+fn untapped_creatures(game: &Game, player: PlayerId) -> Vec<Entity> {
+    let mut query = game.world.query::<(&Object, &Permanent)>();
+
+    let mut creatures: Vec<_> = query
+        .iter()
+        .filter(|(_, (object, permanent))| {
+            object.controller == Some(player)
+                && object.types.contains(&CardType::Creature)
+                && !permanent.tapped
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+
+    // Query order isn't meaningful, so keep results stable for callers.
+    creatures.sort();
+    creatures
+}
+
+fn priority_actions(game: &Game, player: PlayerId) -> Vec<PlayerAction> {
+    let mut actions = vec![PlayerAction::PassPriority];
+
+    let stack_empty = game.zone(ZoneId::Stack).unwrap().is_empty();
+    let main_phase = game.step == Step::Main1 || game.step == Step::Main2;
+    let sorcery_timing = stack_empty && main_phase && game.active_player == player;
+
+    let hand = game.zone(ZoneId::Hand(player)).unwrap().members();
+    for &card in hand {
+        let object = match game.world.get::<Object>(card) {
+            Ok(object) => object,
+            Err(_) => continue,
+        };
+
+        // 116.2a [...] A player can take this action any time they have
+        //        priority and the stack is empty during a main phase of their
+        //        turn.
+        if object.types.contains(&CardType::Land) {
+            let lands_played = game.players.get(player).unwrap().lands_played_this_turn;
+
+            if sorcery_timing && lands_played == 0 {
+                actions.push(PlayerAction::PlayLand { card });
+            }
+
+            continue;
+        }
+
+        // 117.1a A player may cast an instant spell any time they have
+        //        priority. A player may cast a noninstant spell during their
+        //        main phase any time they have priority and the stack is
+        //        empty.
+        if object.mana_cost.is_none() {
+            continue;
+        }
+
+        if !sorcery_timing && !object.types.contains(&CardType::Instant) {
+            continue;
+        }
+
+        // 601.2b [...] If the spell has a variable cost that will be paid as
+        //        it’s being cast (such as an {X} in its mana cost; see rule
+        //        107.3), the player announces the value of that variable.
+        //
+        // Any value up to MAX_GENERIC_MANA can be announced, but only the ones
+        // the player's pool could already pay for are listed.
+        let has_x = object
+            .mana_cost
+            .as_ref()
+            .is_some_and(|cost| cost.items.contains(&ManaCostItem::XGenericMana));
+
+        if has_x {
+            let mana_available = game.players.get(player).unwrap().mana_pool.iter().count();
+
+            for x in 0..=mana_available as u32 {
+                actions.push(PlayerAction::StartCastingSpell {
+                    spell: card,
+                    x: Some(x),
+                });
+            }
+        } else {
+            actions.push(PlayerAction::StartCastingSpell {
+                spell: card,
+                x: None,
+            });
+        }
+    }
+
+    actions
+}
+
+/// Keeping the hand with each described choice of cards to put on the bottom
+/// of the library, as well as taking a mulligan and using any mulligan
+/// abilities of cards in the hand.
+fn mulligan_actions(game: &Game, player: PlayerId) -> Vec<PlayerAction> {
+    let count = super::start::cards_to_bottom(game, player);
+    let hand = game.zone(ZoneId::Hand(player)).unwrap().members();
+
+    let mut actions: Vec<_> = selections(hand, count)
+        .into_iter()
+        .map(|bottom| PlayerAction::KeepHand { bottom })
+        .collect();
+
+    if count < game.config.starting_hand_size {
+        actions.push(PlayerAction::Mulligan);
+    }
+
+    for &card in hand {
+        let has_ability = game
+            .world
+            .get::<Object>(card)
+            .is_ok_and(|object| !object.mulligan_abilities.is_empty());
+
+        if has_ability {
+            actions.push(PlayerAction::UseMulliganAbility { card });
+        }
+    }
+
+    actions
+}
+
+fn spell_payment_actions(game: &Game, player: PlayerId) -> Vec<PlayerAction> {
+    let mut actions = Vec::new();
+    let mana_pool = &game.players.get(player).unwrap().mana_pool;
+
+    let stack = game.zone(ZoneId::Stack).unwrap().members();
+    for &spell in stack {
+        let entity = match game.world.entity(spell) {
+            Ok(entity) => entity,
+            Err(_) => continue,
+        };
+
+        let (object, incomplete) = match (entity.get::<Object>(), entity.get::<IncompleteSpell>()) {
+            (Some(object), Some(incomplete)) => (object, incomplete),
+            _ => continue,
+        };
+
+        if object.controller != Some(player) {
+            continue;
+        }
+
+        if incomplete.is_paid(mana_pool) {
+            actions.push(PlayerAction::FinishCastingSpell { spell });
+        } else {
+            for (mana_id, mana) in mana_pool.iter() {
+                let mut mana_paid: Vec<_> = incomplete.mana_paid().collect();
+                if mana_paid.contains(&mana_id) || !mana.can_be_spent_on(&object) {
+                    continue;
+                }
+                mana_paid.push(mana_id);
+
+                let payable = super::casting::assign_spell_mana(
+                    &incomplete,
+                    mana_pool,
+                    &mana_paid,
+                    &[],
+                    false,
+                )
+                .is_some();
+
+                if payable {
+                    actions.push(PlayerAction::PayIncompleteSpellMana {
+                        spell,
+                        mana: mana_id,
+                    });
+                }
+            }
+
+            let life = game.players.get(player).unwrap().life;
+            let life_needed = incomplete.life_paid() + PHYREXIAN_LIFE;
+
+            if super::casting::life_payment(&incomplete, mana_pool).is_some()
+                && life >= life_needed as i64
+            {
+                actions.push(PlayerAction::PayIncompleteSpellLife { spell });
+            }
+
+            if super::casting::auto_payment(game, player, spell).is_ok() {
+                actions.push(PlayerAction::AutoPaySpell { spell });
+            }
+        }
+
+        actions.push(PlayerAction::CancelCastingSpell { spell });
+    }
+
+    actions
+}
+
+/// Describe declarations of attackers or blockers: nothing, each creature on
+/// its own with each choice, and every creature at once with the same choice.
+fn declarations<C: Copy, D>(
+    creatures: Vec<Entity>,
+    choices: &[C],
+    declare: impl Fn(Entity, C) -> D,
+    make_action: impl Fn(Vec<D>) -> PlayerAction,
+) -> Vec<PlayerAction> {
+    let mut actions = vec![make_action(Vec::new())];
+
+    for &creature in &creatures {
+        for &choice in choices {
+            actions.push(make_action(vec![declare(creature, choice)]));
+        }
+    }
+
+    if creatures.len() > 1 {
+        for &choice in choices {
+            let declarations = creatures
+                .iter()
+                .map(|&creature| declare(creature, choice))
+                .collect();
+            actions.push(make_action(declarations));
+        }
+    }
+
+    actions
+}
+
+/// Describe choices of `count` entities from `entities`: each run of `count`
+/// consecutive entities, which includes every entity at least once.
+fn selections(entities: &[Entity], count: usize) -> Vec<Vec<Entity>> {
+    if count == 0 {
+        return vec![Vec::new()];
+    }
+
+    entities.windows(count).map(<[Entity]>::to_vec).collect()
+}
@@ -0,0 +1,45 @@
+//! Emptying mana pools as steps and phases end.
+
+use crate::mana_pool::{UnspentManaEffect, UnspentManaModification};
+
+use super::Game;
+
+/// Empty every player's mana pool, except for mana that an effect keeps in
+/// the pool or turns into colorless mana.
+pub fn empty_mana_pools(game: &mut Game) {
+    let effects: Vec<_> = game
+        .world
+        .query::<(&UnspentManaEffect,)>()
+        .iter()
+        .map(|(_entity, (effect,))| (effect.player, effect.modification))
+        .collect();
+
+    for player in &mut game.players {
+        let modifications: Vec<_> = effects
+            .iter()
+            .filter(|(affected, _)| *affected == player.id)
+            .map(|&(_, modification)| modification)
+            .collect();
+
+        player.mana_pool.retain(|_id, mana| {
+            let kept = modifications.iter().any(|modification| match modification {
+                UnspentManaModification::Keep => true,
+                UnspentManaModification::KeepColor(color) => mana.color == Some(*color),
+                UnspentManaModification::BecomeColorless => false,
+            });
+
+            if kept {
+                return true;
+            }
+
+            // Mana that becomes colorless is the same mana as before, so it
+            // keeps its ID, source and restrictions.
+            if modifications.contains(&UnspentManaModification::BecomeColorless) {
+                mana.color = None;
+                return true;
+            }
+
+            false
+        });
+    }
+}
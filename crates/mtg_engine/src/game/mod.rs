@@ -1,7 +1,29 @@
 mod casting;
+mod cleanup;
 mod combat;
+mod config;
+mod damage;
+mod effects;
+#[allow(clippy::module_inception)]
 mod game;
+mod legal_actions;
+mod mana_pools;
+mod multiplayer;
+mod replacement;
+mod replay;
+mod sagas;
+mod save;
+mod snapshot;
+mod start;
 mod state_based_actions;
+mod triggers;
 pub mod util;
+mod view;
 
+pub use config::{GameConfig, PlayerConfig};
 pub use game::*;
+pub use replay::{ActionLog, Checkpoint, Divergence};
+pub use save::{LoadError, SAVE_VERSION};
+pub use snapshot::GameSnapshot;
+pub use start::StartError;
+pub use view::{ObjectView, PlayerView, Viewer, ZoneView};
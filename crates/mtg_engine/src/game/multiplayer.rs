@@ -0,0 +1,218 @@
+//! 800. General (Multiplayer Rules)
+//!
+//! 800.4. Unlike two-player games, multiplayer games can continue after one or
+//!        more players have left the game.
+
+use hecs::{Entity, EntityRef};
+
+use crate::action::PlayerActionCategory;
+use crate::components::{Attacking, Card, Object};
+use crate::hand_size::MaxHandSizeEffect;
+use crate::mana_pool::UnspentManaEffect;
+use crate::player::PlayerId;
+use crate::replacement::ReplacementEffect;
+use crate::target::Target;
+use crate::zone::ZoneId;
+
+use super::{Game, GameState};
+
+/// 800.4a When a player leaves the game, all objects (see rule 109) owned by
+///        that player leave the game and any effects which give that player
+///        control of any objects or players end. Then, if that player
+///        controlled any objects on the stack not represented by cards, those
+///        objects cease to exist. Then, if there are any objects still
+///        controlled by that player, those objects are exiled. This is not a
+///        state-based action. It happens as soon as the player leaves the
+///        game. [...]
+pub fn leave_game(game: &mut Game, player: PlayerId) {
+    log::debug!("Player {:?} is leaving the game", player);
+
+    let owned: Vec<_> = game
+        .world
+        .query::<(&Object,)>()
+        .iter()
+        .filter(|(_, (object,))| object.owner == player)
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in owned {
+        remove_object(game, entity);
+    }
+
+    let abilities: Vec<_> = game.zones[&ZoneId::Stack]
+        .members()
+        .iter()
+        .copied()
+        .filter(|&entity| {
+            let entity_ref = match game.world.entity(entity) {
+                Ok(entity_ref) => entity_ref,
+                Err(_) => return false,
+            };
+
+            let controlled = match entity_ref.get::<Object>() {
+                Some(object) => object.controller == Some(player),
+                None => false,
+            };
+
+            controlled && entity_ref.get::<Card>().is_none()
+        })
+        .collect();
+
+    for entity in abilities {
+        remove_object(game, entity);
+    }
+
+    let controlled: Vec<_> = game
+        .world
+        .query::<(&Object,)>()
+        .iter()
+        .filter(|(_, (object,))| object.controller == Some(player))
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for entity in controlled {
+        game.move_object_to_zone(entity, ZoneId::Exile);
+    }
+
+    // Effects that only apply to the player, like ones that keep their unspent
+    // mana, have nothing left to apply to.
+    let effects: Vec<_> = game
+        .world
+        .iter()
+        .filter(|entity| only_affects(*entity, player))
+        .map(|entity| entity.entity())
+        .collect();
+
+    for entity in effects {
+        let _ = game.world.despawn(entity);
+    }
+
+    // Abilities that haven't been put on the stack yet would cease to exist as
+    // soon as they were.
+    game.pending_triggers
+        .retain(|trigger| trigger.controller != player);
+
+    // Events waiting on the player to choose a replacement effect only affect
+    // the player and their objects, which are gone now.
+    game.pending_replacements
+        .retain(|pending| pending.player != player);
+
+    let world = &game.world;
+    game.spell_proposals
+        .retain(|(spell, _)| world.contains(*spell));
+
+    game.players_that_have_passed.remove(&player);
+
+    // Creatures attacking the player or their planeswalkers have nothing left
+    // to attack.
+    let attackers: Vec<_> = game
+        .world
+        .query::<(&Attacking,)>()
+        .iter()
+        .filter(|(_, (attacking,))| {
+            super::combat::defending_player(game, attacking.target)
+                .is_none_or(|defender| defender == player)
+        })
+        .map(|(entity, _)| entity)
+        .collect();
+
+    for attacker in attackers {
+        super::combat::remove_from_combat(game, attacker);
+    }
+}
+
+/// Whether an entity is an effect that isn't part of an object and only
+/// applies to the given player or is carried out by them.
+fn only_affects(entity: EntityRef, player: PlayerId) -> bool {
+    if entity.has::<Object>() {
+        return false;
+    }
+
+    let unspent_mana = entity
+        .get::<UnspentManaEffect>()
+        .is_some_and(|effect| effect.player == player);
+    let hand_size = entity
+        .get::<MaxHandSizeEffect>()
+        .is_some_and(|effect| effect.player == player);
+    let replacement = entity
+        .get::<ReplacementEffect>()
+        .is_some_and(|effect| match &*effect {
+            ReplacementEffect::EntersTapped { player: affected } => *affected == player,
+            ReplacementEffect::DrawInstead {
+                player: affected,
+                controller,
+                ..
+            } => *affected == player || *controller == player,
+            ReplacementEffect::ExileInsteadOfGraveyard { player: affected } => {
+                *affected == Some(player)
+            }
+            ReplacementEffect::PreventDamage { target, .. } => *target == Target::Player(player),
+            _ => false,
+        });
+
+    unspent_mana || hand_size || replacement
+}
+
+/// Remove an object from the game entirely, without it moving to another zone.
+fn remove_object(game: &mut Game, entity: Entity) {
+    let zone = match game.world.get::<Object>(entity) {
+        Ok(object) => object.zone,
+        Err(_) => return,
+    };
+
+    if let Some(zone) = game.zones.get_mut(&zone) {
+        zone.remove(entity);
+    }
+
+    let _ = game.world.despawn(entity);
+}
+
+/// Move the game along if it was waiting on a player who has just left it.
+pub fn continue_without(game: &mut Game, player: PlayerId) {
+    let action = match &game.state {
+        GameState::Player {
+            player: waiting_on,
+            action,
+        } if *waiting_on == player => action.clone(),
+        _ => return,
+    };
+
+    match action {
+        // 800.4a [...] If the player who left the game had priority at the
+        //        time they left, priority passes to the next player in turn
+        //        order who’s still in the game.
+        //
+        // Spells that the player was casting left the game with them.
+        PlayerActionCategory::Priority
+        | PlayerActionCategory::SpellManaAbilities
+        | PlayerActionCategory::SpellPayingCost => game.priority_passed(player),
+
+        // Someone who is still in the game chooses who plays first instead.
+        PlayerActionCategory::ChooseStartingPlayer => super::start::ask_for_starting_player(game),
+        PlayerActionCategory::Mulligan => super::start::ask_for_mulligan_decision(game),
+
+        // 800.4j If a player leaves the game during their turn, that turn
+        //        continues to its completion without an active player. [...]
+        PlayerActionCategory::ChooseAttackers => game.start_priority_round(game.active_player),
+        PlayerActionCategory::DiscardToHandSize => super::cleanup::finish_cleanup(game),
+
+        PlayerActionCategory::ChooseBlockers => super::combat::ask_next_defender(game, player),
+
+        // The player's pending choices were dropped when they left, so the
+        // game goes back to what it was doing before, which may also have been
+        // waiting on them.
+        PlayerActionCategory::ChooseReplacementEffect { .. } => {
+            super::replacement::request_choices(game);
+
+            if !matches!(
+                game.state,
+                GameState::Player {
+                    action: PlayerActionCategory::ChooseReplacementEffect { .. },
+                    ..
+                }
+            ) {
+                continue_without(game, player);
+            }
+        }
+    }
+}
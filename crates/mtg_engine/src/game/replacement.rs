@@ -0,0 +1,427 @@
+//! 614. Replacement Effects
+//!
+//! 615. Prevention Effects
+//!
+//! 616. Interaction of Replacement and/or Prevention Effects
+
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::action::{ActionError, PlayerActionCategory};
+use crate::components::Object;
+use crate::effect::Effect;
+use crate::player::PlayerId;
+use crate::replacement::{ReplaceableEvent, ReplacementEffect};
+use crate::target::Target;
+use crate::zone::ZoneId;
+
+use super::{Game, GameState};
+
+/// An event that is waiting for a player to choose which of several
+/// replacement effects to apply to it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingReplacement {
+    pub player: PlayerId,
+    pub event: ReplaceableEvent,
+    pub options: Vec<Entity>,
+
+    /// Replacement effects that have already been applied to this event.
+    pub applied: Vec<Entity>,
+}
+
+/// The outcome of applying a replacement effect to an event.
+enum Replaced {
+    /// The event was modified and will happen in its new form.
+    Event(ReplaceableEvent),
+
+    /// The event will not happen and the given effects happen instead.
+    Effects {
+        controller: PlayerId,
+        effects: Vec<Effect>,
+    },
+
+    /// 701.15a [...] The next time [permanent] would be destroyed this turn,
+    ///         instead remove all damage marked on it and its controller taps
+    ///         it.
+    Regenerated { permanent: Entity },
+}
+
+/// Perform an event, giving replacement effects the chance to modify or replace
+/// it first.
+pub fn perform(game: &mut Game, event: ReplaceableEvent) {
+    let applied = game.replacements_applying.clone();
+    process(game, event, applied);
+}
+
+fn process(game: &mut Game, event: ReplaceableEvent, applied: Vec<Entity>) {
+    let options = applicable_effects(game, &event, &applied);
+
+    match options.as_slice() {
+        [] => happen(game, event),
+        [effect] => apply_effect(game, event, applied, *effect),
+        _ => {
+            // 616.1. If two or more replacement and/or prevention effects are
+            //        attempting to modify the way an event affects an object or
+            //        player, the affected object’s controller (or its owner if
+            //        it has no controller) or the affected player chooses one
+            //        to apply, following the steps listed below.
+            //
+            // TODO: Rules 616.1a–d, which require self-replacement effects and
+            // control-changing effects to be chosen first.
+            //
+            // 614.6. [...] the modified event may contain instructions that
+            //        can’t be carried out, in which case the impossible
+            //        instruction is simply ignored.
+            let Some(player) = affected_player(game, &event) else {
+                log::warn!("Nothing is affected by {:?}, so it doesn't happen", event);
+                return;
+            };
+
+            log::debug!(
+                "Player {:?} must choose a replacement effect for {:?}",
+                player,
+                event
+            );
+
+            game.pending_replacements.push_back(PendingReplacement {
+                player,
+                event,
+                options,
+                applied,
+            });
+        }
+    }
+}
+
+/// 614.5. A replacement effect doesn’t invoke itself repeatedly; it gets only
+///        one opportunity to affect an event or any modified events that may
+///        replace that event.
+fn applicable_effects(game: &Game, event: &ReplaceableEvent, applied: &[Entity]) -> Vec<Entity> {
+    let mut query = game.world.query::<(&ReplacementEffect,)>();
+
+    query
+        .iter()
+        .filter(|(entity, _)| !applied.contains(entity))
+        .filter(|(_, (effect,))| applies_to(game, effect, event))
+        .map(|(entity, _)| entity)
+        .collect()
+}
+
+fn applies_to(game: &Game, effect: &ReplacementEffect, event: &ReplaceableEvent) -> bool {
+    match (effect, event) {
+        (
+            ReplacementEffect::EntersTapped { player },
+            ReplaceableEvent::ZoneChange { object, to, tapped },
+        ) => {
+            if *to != ZoneId::Battlefield || *tapped {
+                return false;
+            }
+
+            match game.world.get::<Object>(*object) {
+                Ok(object) => object.controller_on_battlefield() == *player,
+                Err(_) => false,
+            }
+        }
+
+        (
+            ReplacementEffect::DrawInstead { player, .. },
+            ReplaceableEvent::Draw { player: drawing },
+        ) => player == drawing,
+
+        (
+            ReplacementEffect::ExileInsteadOfGraveyard { player },
+            ReplaceableEvent::ZoneChange { to, .. },
+        ) => match (to, player) {
+            (ZoneId::Graveyard(_), None) => true,
+            (ZoneId::Graveyard(owner), Some(player)) => owner == player,
+            _ => false,
+        },
+
+        // 615.6. If damage that would be dealt is prevented, it never happens.
+        (
+            ReplacementEffect::PreventDamage { target, remaining },
+            ReplaceableEvent::Damage {
+                target: damaged,
+                amount,
+                ..
+            },
+        ) => target == damaged && *remaining > 0 && *amount > 0,
+
+        (
+            ReplacementEffect::PreventAllCombatDamage,
+            ReplaceableEvent::Damage { amount, combat, .. },
+        ) => *combat && *amount > 0,
+
+        (
+            ReplacementEffect::Regenerate { permanent },
+            ReplaceableEvent::Destroy {
+                permanent: destroyed,
+            },
+        ) => permanent == destroyed,
+
+        _ => false,
+    }
+}
+
+fn replace(effect: &ReplacementEffect, event: ReplaceableEvent) -> Replaced {
+    match (effect, event) {
+        (
+            ReplacementEffect::EntersTapped { .. },
+            ReplaceableEvent::ZoneChange { object, to, .. },
+        ) => Replaced::Event(ReplaceableEvent::ZoneChange {
+            object,
+            to,
+            tapped: true,
+        }),
+
+        (
+            ReplacementEffect::DrawInstead {
+                controller,
+                effects,
+                ..
+            },
+            ReplaceableEvent::Draw { .. },
+        ) => Replaced::Effects {
+            controller: *controller,
+            effects: effects.clone(),
+        },
+
+        (
+            ReplacementEffect::ExileInsteadOfGraveyard { .. },
+            ReplaceableEvent::ZoneChange { object, tapped, .. },
+        ) => Replaced::Event(ReplaceableEvent::ZoneChange {
+            object,
+            to: ZoneId::Exile,
+            tapped,
+        }),
+
+        (
+            ReplacementEffect::PreventDamage { remaining, .. },
+            ReplaceableEvent::Damage {
+                source,
+                target,
+                amount,
+                combat,
+            },
+        ) => Replaced::Event(ReplaceableEvent::Damage {
+            source,
+            target,
+            amount: amount.saturating_sub(*remaining),
+            combat,
+        }),
+
+        (
+            ReplacementEffect::PreventAllCombatDamage,
+            ReplaceableEvent::Damage {
+                source,
+                target,
+                combat,
+                ..
+            },
+        ) => Replaced::Event(ReplaceableEvent::Damage {
+            source,
+            target,
+            amount: 0,
+            combat,
+        }),
+
+        (ReplacementEffect::Regenerate { .. }, ReplaceableEvent::Destroy { permanent }) => {
+            Replaced::Regenerated { permanent }
+        }
+
+        (effect, event) => {
+            unreachable!("{:?} does not apply to {:?}", effect, event)
+        }
+    }
+}
+
+fn apply_effect(
+    game: &mut Game,
+    event: ReplaceableEvent,
+    mut applied: Vec<Entity>,
+    effect: Entity,
+) {
+    let replacement = game
+        .world
+        .get::<ReplacementEffect>(effect)
+        .ok()
+        .map(|replacement| (*replacement).clone());
+
+    let replacement = match replacement {
+        Some(replacement) => replacement,
+        None => {
+            log::warn!("Replacement effect {:?} no longer exists", effect);
+            process(game, event, applied);
+            return;
+        }
+    };
+
+    log::debug!(
+        "Applying replacement effect {:?} to {:?}",
+        replacement,
+        event
+    );
+    applied.push(effect);
+    use_up(game, effect, &replacement, &event);
+
+    // 614.6. If an event is replaced, it never happens. A modified event
+    //        occurs instead, which may in turn trigger abilities. Note that the
+    //        modified event may contain instructions that can’t be carried
+    //        out, in which case the impossible instruction is simply ignored.
+    match replace(&replacement, event) {
+        Replaced::Event(event) => process(game, event, applied),
+        Replaced::Effects {
+            controller,
+            effects,
+        } => {
+            // Any events that happen as part of these effects are modified
+            // events, so the effects that already applied can't apply again.
+            let previous = std::mem::replace(&mut game.replacements_applying, applied);
+            super::effects::apply(game, controller, &effects);
+            game.replacements_applying = previous;
+        }
+        Replaced::Regenerated { permanent } => super::damage::regenerate(game, permanent),
+    }
+}
+
+/// Update or remove effects that only apply a limited number of times, like
+/// prevention and regeneration shields, once they've been applied to an event.
+fn use_up(
+    game: &mut Game,
+    effect: Entity,
+    replacement: &ReplacementEffect,
+    event: &ReplaceableEvent,
+) {
+    match (replacement, event) {
+        // 615.7. [...] Each 1 damage that’s prevented reduces the remaining
+        //        shield by 1. When the shield is reduced to 0, the effect
+        //        ends.
+        (
+            ReplacementEffect::PreventDamage { target, remaining },
+            ReplaceableEvent::Damage { amount, .. },
+        ) => {
+            let remaining = remaining.saturating_sub(*amount);
+
+            if remaining == 0 {
+                let _ = game.world.despawn(effect);
+            } else {
+                let updated = ReplacementEffect::PreventDamage {
+                    target: *target,
+                    remaining,
+                };
+                game.world.insert_one(effect, updated).unwrap();
+            }
+        }
+
+        // A regeneration shield is used up once it replaces a destruction.
+        (ReplacementEffect::Regenerate { .. }, _) => {
+            let _ = game.world.despawn(effect);
+        }
+
+        _ => {}
+    }
+}
+
+/// Carry out an event once all replacement effects have been applied to it.
+fn happen(game: &mut Game, event: ReplaceableEvent) {
+    match event {
+        ReplaceableEvent::Draw { player } => game.apply_draw(player),
+        ReplaceableEvent::ZoneChange { object, to, tapped } => {
+            game.apply_zone_change(object, to, tapped);
+        }
+        ReplaceableEvent::Damage {
+            source,
+            target,
+            amount,
+            combat,
+        } => super::damage::apply_damage(game, source, target, amount, combat),
+        ReplaceableEvent::Destroy { permanent } => super::damage::apply_destroy(game, permanent),
+    }
+}
+
+/// The player who chooses which replacement effect applies to an event, or
+/// `None` if the object it affects no longer exists.
+fn affected_player(game: &Game, event: &ReplaceableEvent) -> Option<PlayerId> {
+    match event {
+        ReplaceableEvent::Draw { player } => Some(*player),
+        ReplaceableEvent::Damage {
+            target: Target::Player(player),
+            ..
+        } => Some(*player),
+        ReplaceableEvent::ZoneChange { object, .. }
+        | ReplaceableEvent::Destroy { permanent: object }
+        | ReplaceableEvent::Damage {
+            target: Target::Object(object),
+            ..
+        } => {
+            let object = game.world.get::<Object>(*object).ok()?;
+            Some(object.controller.unwrap_or(object.owner))
+        }
+    }
+}
+
+/// Ask the next player to choose a replacement effect if any events are
+/// waiting on a choice. Once every choice has been made, the game continues
+/// from where it was before the choices were needed.
+pub fn request_choices(game: &mut Game) {
+    if matches!(game.state, GameState::Complete(_)) {
+        game.pending_replacements.clear();
+        game.state_before_replacements = None;
+        return;
+    }
+
+    match game.pending_replacements.front() {
+        Some(pending) => {
+            if game.state_before_replacements.is_none() {
+                game.state_before_replacements = Some(game.state.clone());
+            }
+
+            game.state = GameState::Player {
+                player: pending.player,
+                action: PlayerActionCategory::ChooseReplacementEffect {
+                    options: pending.options.clone(),
+                },
+            };
+        }
+        None => {
+            if let Some(state) = game.state_before_replacements.take() {
+                match state {
+                    // Events that were waiting on choices may have caused
+                    // state-based actions or triggers, so they need to be
+                    // checked again before the player receives priority.
+                    GameState::Player {
+                        player,
+                        action: PlayerActionCategory::Priority,
+                    } => game.give_priority(player),
+                    state => game.state = state,
+                }
+            }
+        }
+    }
+}
+
+/// 616.1e Any of the applicable replacement and/or prevention effects may be
+///        chosen.
+///
+/// 616.1f Once the chosen effect has been applied, this process is repeated
+///        (taking into account only replacement or prevention effects that
+///        would now be applicable) until there are no more left to apply.
+pub fn choose_replacement_effect(
+    game: &mut Game,
+    player: PlayerId,
+    effect: Entity,
+) -> Result<(), ActionError> {
+    let pending = match game.pending_replacements.front() {
+        Some(pending) if pending.player == player => pending,
+        _ => return Err(ActionError::NotChoosingReplacementEffect),
+    };
+
+    if !pending.options.contains(&effect) {
+        return Err(ActionError::ReplacementEffectNotAnOption { effect });
+    }
+
+    let pending = game.pending_replacements.pop_front().unwrap();
+    apply_effect(game, pending.event, pending.applied, effect);
+
+    Ok(())
+}
@@ -0,0 +1,225 @@
+//! Recording the actions taken in a game so that the game can be played back
+//! exactly, and checking that a replay reaches the same states as the original
+//! game did.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::action::{ActionError, PlayerAction};
+use crate::object_db::ObjectDb;
+use crate::player::PlayerId;
+
+use super::config::GameConfig;
+use super::snapshot::{self, GameSnapshot};
+use super::Game;
+
+/// Everything needed to play a game back from the beginning: how the game was
+/// set up, and every action that was successfully taken in it.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ActionLog {
+    /// How the game was set up, including the seed of its random number
+    /// generator.
+    pub config: GameConfig,
+
+    /// The game as it was just before the first action was taken, including
+    /// the state of the random number generator.
+    pub setup: GameSnapshot,
+
+    pub actions: Vec<(PlayerId, PlayerAction)>,
+}
+
+/// The state of a game after a number of actions from its log were taken,
+/// used to check that a replay of the game hasn't diverged from it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    /// How many actions from the log had been taken.
+    pub actions: usize,
+
+    pub state: Value,
+}
+
+/// The first point where a replayed game stopped matching the original.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Divergence {
+    /// An action that succeeded in the original game failed during the replay.
+    ActionFailed {
+        index: usize,
+        player: PlayerId,
+        action: PlayerAction,
+        error: ActionError,
+    },
+
+    /// After taking `actions` actions, the replayed game is different from the
+    /// checkpoint. `path` points to the first part of the state that differs.
+    StateMismatch { actions: usize, path: String },
+
+    /// A checkpoint was taken after more actions than the log contains.
+    MissingActions { checkpoint: usize, actions: usize },
+
+    /// The log's setup describes a game that can't exist, like one with no
+    /// players, so nothing could be played back.
+    InvalidSetup { reason: String },
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::ActionFailed {
+                index,
+                player,
+                action,
+                error,
+            } => write!(
+                formatter,
+                "action {} ({:?} by {:?}) failed: {}",
+                index, action, player, error
+            ),
+            Self::StateMismatch { actions, path } => write!(
+                formatter,
+                "state differs at {} after {} actions",
+                path, actions
+            ),
+            Self::MissingActions {
+                checkpoint,
+                actions,
+            } => write!(
+                formatter,
+                "checkpoint after {} actions, but the log only has {}",
+                checkpoint, actions
+            ),
+            Self::InvalidSetup { reason } => write!(formatter, "invalid setup: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Divergence {}
+
+/// Called before every action, so that the log begins with the game exactly as
+/// it was set up.
+pub fn start_recording(game: &mut Game) {
+    if game.action_log.is_none() {
+        game.action_log = Some(ActionLog {
+            config: game.config.clone(),
+            setup: snapshot::snapshot(game),
+            actions: Vec::new(),
+        });
+    }
+}
+
+pub fn record(game: &mut Game, player: PlayerId, action: PlayerAction) {
+    if let Some(log) = &mut game.action_log {
+        log.actions.push((player, action));
+    }
+}
+
+pub fn checkpoint(game: &Game) -> Checkpoint {
+    let actions = game
+        .action_log
+        .as_ref()
+        .map(|log| log.actions.len())
+        .unwrap_or(0);
+
+    Checkpoint {
+        actions,
+        state: state_value(game),
+    }
+}
+
+/// Play back every action in the log against a new game, checking the game
+/// against each checkpoint along the way.
+pub fn replay(
+    log: &ActionLog,
+    checkpoints: &[Checkpoint],
+    object_db: ObjectDb,
+) -> Result<Game, Divergence> {
+    let invalid = |reason: String| Divergence::InvalidSetup { reason };
+
+    let mut game =
+        Game::blank(log.config.clone(), object_db).map_err(|err| invalid(err.to_string()))?;
+    snapshot::restore(&mut game, log.setup.clone());
+    snapshot::validate(&game).map_err(invalid)?;
+
+    let mut checkpoints: Vec<_> = checkpoints.iter().collect();
+    checkpoints.sort_by_key(|checkpoint| checkpoint.actions);
+
+    if let Some(checkpoint) = checkpoints.last() {
+        if checkpoint.actions > log.actions.len() {
+            return Err(Divergence::MissingActions {
+                checkpoint: checkpoint.actions,
+                actions: log.actions.len(),
+            });
+        }
+    }
+
+    let mut checkpoints = checkpoints.into_iter().peekable();
+
+    for index in 0..=log.actions.len() {
+        while let Some(checkpoint) = checkpoints.next_if(|c| c.actions == index) {
+            if let Some(path) = first_difference(&checkpoint.state, &state_value(&game)) {
+                return Err(Divergence::StateMismatch {
+                    actions: index,
+                    path,
+                });
+            }
+        }
+
+        if let Some((player, action)) = log.actions.get(index) {
+            game.do_action(*player, action.clone())
+                .map_err(|error| Divergence::ActionFailed {
+                    index,
+                    player: *player,
+                    action: action.clone(),
+                    error,
+                })?;
+        }
+    }
+
+    Ok(game)
+}
+
+fn state_value(game: &Game) -> Value {
+    serde_json::to_value(snapshot::snapshot(game)).expect("game state should always serialize")
+}
+
+/// Finds the first place where two values differ, described as a JSON pointer.
+fn first_difference(expected: &Value, actual: &Value) -> Option<String> {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            let mut keys: Vec<_> = expected.keys().chain(actual.keys()).collect();
+            keys.sort();
+            keys.dedup();
+
+            keys.into_iter()
+                .find_map(|key| match (expected.get(key), actual.get(key)) {
+                    (Some(expected), Some(actual)) => {
+                        first_difference(expected, actual).map(|path| format!("/{}{}", key, path))
+                    }
+                    _ => Some(format!("/{}", key)),
+                })
+        }
+
+        (Value::Array(expected), Value::Array(actual)) => {
+            let differing_item =
+                expected
+                    .iter()
+                    .zip(actual)
+                    .enumerate()
+                    .find_map(|(index, (expected, actual))| {
+                        first_difference(expected, actual).map(|path| format!("/{}{}", index, path))
+                    });
+
+            match differing_item {
+                Some(path) => Some(path),
+                None if expected.len() != actual.len() => {
+                    Some(format!("/{}", expected.len().min(actual.len())))
+                }
+                None => None,
+            }
+        }
+
+        (expected, actual) if expected == actual => None,
+        _ => Some(String::new()),
+    }
+}
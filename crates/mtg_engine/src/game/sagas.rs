@@ -0,0 +1,141 @@
+//! 714. Saga Cards
+
+use hecs::Entity;
+
+use crate::components::{Counters, Object, Permanent, TriggeredAbility};
+use crate::counters::Counter;
+use crate::zone::ZoneId;
+
+use super::Game;
+
+/// 714.3a As a Saga enters the battlefield, its controller puts a lore counter
+///        on it. Effects that state a Saga enters with a certain number of
+///        lore counters replace this rule.
+pub fn enter_battlefield(game: &mut Game, permanent: Entity) {
+    if is_saga(game, permanent) {
+        add_lore_counters(game, permanent, 1);
+    }
+}
+
+/// 714.3b As a player’s precombat main phase begins, that player puts a lore
+///        counter on each Saga they control. This turn-based action doesn’t
+///        use the stack.
+pub fn enter_precombat_main(game: &mut Game) {
+    let mut sagas = Vec::new();
+
+    let mut query = game.world.query::<(&Object,)>().with::<Permanent>();
+    for (entity, (object,)) in query.iter() {
+        if object.controller == Some(game.active_player) && object.has_subtype("Saga") {
+            sagas.push(entity);
+        }
+    }
+    drop(query);
+
+    for saga in sagas {
+        add_lore_counters(game, saga, 1);
+    }
+}
+
+/// Put lore counters on a Saga and trigger any of its chapter abilities whose
+/// chapter number was reached.
+pub fn add_lore_counters(game: &mut Game, saga: Entity, amount: usize) {
+    if game.world.get::<Counters>(saga).is_err() {
+        game.world.insert_one(saga, Counters::new()).unwrap();
+    }
+
+    let (before, after) = {
+        let mut counters = game.world.get_mut::<Counters>(saga).unwrap();
+        let before = counters.count(&Counter::Lore) as u32;
+        counters.add(Counter::Lore, amount);
+
+        (before, counters.count(&Counter::Lore) as u32)
+    };
+
+    let object = match game.world.get::<Object>(saga) {
+        Ok(object) => object,
+        Err(_) => return,
+    };
+
+    let controller = match object.controller {
+        Some(controller) => controller,
+        None => return,
+    };
+
+    let mut triggered = Vec::new();
+    for ability in &object.chapter_abilities {
+        for &chapter in &ability.chapters {
+            if before < chapter && after >= chapter {
+                triggered.push(TriggeredAbility {
+                    source: saga,
+                    controller,
+                    effects: ability.effects.clone(),
+                    chapter: Some(chapter),
+                });
+            }
+        }
+    }
+    drop(object);
+
+    // Chapter abilities trigger in the order of their chapter numbers.
+    triggered.sort_by_key(|trigger| trigger.chapter);
+    game.pending_triggers.extend(triggered);
+}
+
+/// 714.4. If the number of lore counters on a Saga permanent is greater than or
+///        equal to its final chapter number, and it isn’t the source of a
+///        chapter ability that has triggered but not yet left the stack, that
+///        Saga’s controller sacrifices it. This state-based action doesn’t use
+///        the stack.
+pub fn sagas_to_sacrifice(game: &Game) -> Vec<Entity> {
+    let mut output = Vec::new();
+
+    let mut query = game
+        .world
+        .query::<(&Object, &Counters)>()
+        .with::<Permanent>();
+
+    for (entity, (object, counters)) in query.iter() {
+        if !object.has_subtype("Saga") {
+            continue;
+        }
+
+        let lore = counters.count(&Counter::Lore) as u32;
+        if lore < object.final_chapter_number() {
+            continue;
+        }
+
+        if has_chapter_ability_waiting(game, entity) {
+            continue;
+        }
+
+        output.push(entity);
+    }
+
+    output
+}
+
+fn is_saga(game: &Game, entity: Entity) -> bool {
+    match game.world.get::<Object>(entity) {
+        Ok(object) => object.has_subtype("Saga"),
+        Err(_) => false,
+    }
+}
+
+/// Tells whether a chapter ability of the given Saga has triggered but not yet
+/// left the stack.
+fn has_chapter_ability_waiting(game: &Game, saga: Entity) -> bool {
+    let is_chapter_of_saga =
+        |trigger: &TriggeredAbility| trigger.source == saga && trigger.chapter.is_some();
+
+    if game.pending_triggers.iter().any(is_chapter_of_saga) {
+        return true;
+    }
+
+    let stack = game.zone(ZoneId::Stack).unwrap();
+    stack.members().iter().any(|&entity| {
+        game.world
+            .get::<TriggeredAbility>(entity)
+            .map(|trigger| is_chapter_of_saga(&trigger))
+            .unwrap_or(false)
+    })
+}
@@ -0,0 +1,106 @@
+//! Saving games to JSON and loading them back, so that a game can be stopped
+//! at any moment and continued later.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+use crate::object_db::ObjectDb;
+
+use super::config::GameConfig;
+use super::replay::ActionLog;
+use super::snapshot::{self, GameSnapshot};
+use super::Game;
+
+/// The version of the save format written by [`save`]. Bump this whenever a
+/// change to the game's structure means that older saves can't be loaded.
+pub const SAVE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct SavedGame {
+    version: u32,
+    config: GameConfig,
+    state: GameSnapshot,
+    #[serde(default)]
+    action_log: Option<ActionLog>,
+}
+
+/// Only the version of a save, read before the rest of the save so that saves
+/// from other versions can be reported clearly instead of failing to parse.
+#[derive(Deserialize)]
+struct SaveHeader {
+    version: u32,
+}
+
+#[derive(Debug)]
+pub enum LoadError {
+    /// The save was written by a different version of the save format.
+    UnsupportedVersion { version: u32 },
+
+    /// The save isn't valid JSON, or doesn't describe a game.
+    Json(serde_json::Error),
+
+    /// The save describes a game that can't exist, like one with no players
+    /// or with zones that belong to players who aren't in it.
+    InvalidGame { reason: String },
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::UnsupportedVersion { version } => write!(
+                formatter,
+                "save version {} is not supported (expected version {})",
+                version, SAVE_VERSION
+            ),
+            Self::Json(err) => write!(formatter, "invalid save: {}", err),
+            Self::InvalidGame { reason } => write!(formatter, "invalid save: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::UnsupportedVersion { .. } | Self::InvalidGame { .. } => None,
+            Self::Json(err) => Some(err),
+        }
+    }
+}
+
+impl From<serde_json::Error> for LoadError {
+    fn from(err: serde_json::Error) -> Self {
+        Self::Json(err)
+    }
+}
+
+pub fn save(game: &Game) -> serde_json::Result<String> {
+    let saved = SavedGame {
+        version: SAVE_VERSION,
+        config: game.config.clone(),
+        state: snapshot::snapshot(game),
+        action_log: game.action_log.clone(),
+    };
+
+    serde_json::to_string(&saved)
+}
+
+pub fn load(source: &str, object_db: ObjectDb) -> Result<Game, LoadError> {
+    let header: SaveHeader = serde_json::from_str(source)?;
+    if header.version != SAVE_VERSION {
+        return Err(LoadError::UnsupportedVersion {
+            version: header.version,
+        });
+    }
+
+    let saved: SavedGame = serde_json::from_str(source)?;
+
+    let invalid = |reason: String| LoadError::InvalidGame { reason };
+
+    let mut game = Game::blank(saved.config, object_db).map_err(|err| invalid(err.to_string()))?;
+    snapshot::restore(&mut game, saved.state);
+    snapshot::validate(&game).map_err(invalid)?;
+    game.action_log = saved.action_log;
+
+    Ok(game)
+}
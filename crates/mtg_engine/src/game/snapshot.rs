@@ -0,0 +1,466 @@
+//! 726. Handling Illegal Actions
+//!
+//! 726.1. If a player takes an illegal action or starts to take an action but
+//!        can’t legally complete it, the entire action is reversed and any
+//!        payments already made are canceled. No abilities trigger and no
+//!        effects apply as a result of an undone action. If the action was
+//!        casting a spell, the spell returns to the zone it came from.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+
+use hecs::serialize::row::{self, DeserializeContext, SerializeContext};
+use hecs::{BuiltEntityClone, Entity, EntityBuilder, EntityBuilderClone, EntityRef, World};
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::de::{self, MapAccess};
+use serde::ser::SerializeMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::components::{
+    AttachedToEntity, Attacking, Blocking, Card, Counters, Damage, IncompleteSpell, Object,
+    Permanent, Revealed, Timestamp, TriggeredAbility, UntilEotEffect,
+};
+use crate::hand_size::MaxHandSizeEffect;
+use crate::mana_pool::UnspentManaEffect;
+use crate::player::{PlayerId, Players};
+use crate::pt::{AdjustPtEffect, SetPtEffect, SwitchPtEffect};
+use crate::replacement::ReplacementEffect;
+use crate::zone::{Zone, ZoneId};
+
+use super::replacement::PendingReplacement;
+use super::start::Mulligans;
+use super::{Game, GameOutcome, GameState, Step};
+
+/// A copy of everything in a [`Game`] that can change as the game is played,
+/// which can be used to return the game to an earlier moment.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    entities: SnapshotEntities,
+    next_timestamp: u64,
+    players: Players,
+    starting_player: PlayerId,
+    mulligans: Mulligans,
+    #[serde(with = "rng")]
+    rng: ChaCha8Rng,
+    turn_number: u64,
+    players_that_have_passed: BTreeSet<PlayerId>,
+    active_player: PlayerId,
+    step: Step,
+    repeat_cleanup_step: bool,
+    state: GameState,
+    #[serde(with = "zones")]
+    zones: HashMap<ZoneId, Zone>,
+    pending_triggers: Vec<TriggeredAbility>,
+    pending_replacements: VecDeque<PendingReplacement>,
+    state_before_replacements: Option<GameState>,
+    replacements_applying: Vec<Entity>,
+    spell_proposals: Vec<(Entity, GameSnapshot)>,
+}
+
+/// Invokes the given macro with every component type that can be added to the
+/// world. Every component type needs to be listed here, otherwise it would be
+/// lost when a snapshot is restored or a game is saved.
+macro_rules! with_components {
+    ($callback:ident) => {
+        $callback!(
+            Object,
+            Card,
+            Permanent,
+            IncompleteSpell,
+            Counters,
+            Damage,
+            Attacking,
+            Blocking,
+            TriggeredAbility,
+            UntilEotEffect,
+            Timestamp,
+            AttachedToEntity,
+            SetPtEffect,
+            AdjustPtEffect,
+            SwitchPtEffect,
+            MaxHandSizeEffect,
+            UnspentManaEffect,
+            ReplacementEffect,
+            Revealed
+        )
+    };
+}
+
+/// Copies every component attached to an entity.
+fn clone_entity(entity: EntityRef) -> BuiltEntityClone {
+    let mut builder = EntityBuilderClone::new();
+
+    macro_rules! clone_components {
+        ($($component:ident),*) => {
+            $(
+                if let Some(component) = entity.get::<$component>() {
+                    builder.add((*component).clone());
+                }
+            )*
+        };
+    }
+
+    with_components!(clone_components);
+
+    // A component that isn't listed in `with_components!` is a bug, but losing
+    // it is better than losing the whole game, so it's only logged. The
+    // snapshots_keep_every_component test lists every component type.
+    let missing = entity.len() - builder.component_types().count();
+    if missing > 0 {
+        log::error!(
+            "entity {:?} has {} components that can't be snapshotted",
+            entity.entity(),
+            missing
+        );
+    }
+
+    builder.build()
+}
+
+pub fn snapshot(game: &Game) -> GameSnapshot {
+    let entities = game
+        .world
+        .iter()
+        .map(|entity| (entity.entity(), clone_entity(entity)))
+        .collect();
+
+    GameSnapshot {
+        entities: SnapshotEntities(entities),
+        next_timestamp: game.next_timestamp,
+        players: game.players.clone(),
+        starting_player: game.starting_player,
+        mulligans: game.mulligans.clone(),
+        rng: game.rng.clone(),
+        turn_number: game.turn_number,
+        players_that_have_passed: game.players_that_have_passed.clone(),
+        active_player: game.active_player,
+        step: game.step,
+        repeat_cleanup_step: game.repeat_cleanup_step,
+        state: game.state.clone(),
+        zones: game.zones.clone(),
+        pending_triggers: game.pending_triggers.clone(),
+        pending_replacements: game.pending_replacements.clone(),
+        state_before_replacements: game.state_before_replacements.clone(),
+        replacements_applying: game.replacements_applying.clone(),
+        spell_proposals: game.spell_proposals.clone(),
+    }
+}
+
+/// Return the game to the moment that the snapshot was taken. Entities keep
+/// the same IDs that they had at that moment.
+pub fn restore(game: &mut Game, snapshot: GameSnapshot) {
+    // Despawning every entity instead of clearing the world keeps the IDs of
+    // entities created since the snapshot from ever referring to anything
+    // again, even once the entities from the snapshot are respawned.
+    let current: Vec<_> = game.world.iter().map(|entity| entity.entity()).collect();
+    for entity in current {
+        game.world.despawn(entity).unwrap();
+    }
+
+    for (entity, components) in &snapshot.entities.0 {
+        game.world.spawn_at(*entity, components);
+    }
+
+    game.next_timestamp = snapshot.next_timestamp;
+    game.players = snapshot.players;
+    game.starting_player = snapshot.starting_player;
+    game.mulligans = snapshot.mulligans;
+    game.rng = snapshot.rng;
+    game.turn_number = snapshot.turn_number;
+    game.players_that_have_passed = snapshot.players_that_have_passed;
+    game.active_player = snapshot.active_player;
+    game.step = snapshot.step;
+    game.repeat_cleanup_step = snapshot.repeat_cleanup_step;
+    game.state = snapshot.state;
+    game.zones = snapshot.zones;
+    game.pending_triggers = snapshot.pending_triggers;
+    game.pending_replacements = snapshot.pending_replacements;
+    game.state_before_replacements = snapshot.state_before_replacements;
+    game.replacements_applying = snapshot.replacements_applying;
+    game.spell_proposals = snapshot.spell_proposals;
+}
+
+/// Check that every player, zone and object that a restored game refers to
+/// exists, for snapshots that come from outside the game, like from a save.
+/// Returns what's wrong with the game otherwise.
+pub fn validate(game: &Game) -> Result<(), String> {
+    if game.players.len() != game.config.players.len() {
+        return Err(format!(
+            "the game has {} players, but its config has {}",
+            game.players.len(),
+            game.config.players.len()
+        ));
+    }
+
+    // Players are looked up by their position in turn order.
+    let out_of_order = game
+        .players
+        .iter()
+        .enumerate()
+        .any(|(index, player)| player.id.to_u32() as usize != index);
+    if out_of_order {
+        return Err("the players' IDs aren't in turn order".to_owned());
+    }
+
+    let mut players = vec![game.starting_player, game.active_player];
+    players.extend(&game.players_that_have_passed);
+    for state in std::iter::once(&game.state).chain(&game.state_before_replacements) {
+        match state {
+            GameState::Player { player, .. } => players.push(*player),
+            GameState::Complete(GameOutcome::Win { winner }) => players.push(*winner),
+            GameState::Complete(GameOutcome::Draw) => {}
+        }
+    }
+
+    let is_player = |player: PlayerId| game.players.get(player).is_some();
+    if let Some(player) = players.into_iter().find(|&player| !is_player(player)) {
+        return Err(format!("{:?} is not a player in the game", player));
+    }
+
+    let mut zone_ids = vec![
+        ZoneId::Stack,
+        ZoneId::Battlefield,
+        ZoneId::Exile,
+        ZoneId::Command,
+    ];
+    for player in &game.players {
+        zone_ids.extend([
+            ZoneId::Library(player.id),
+            ZoneId::Hand(player.id),
+            ZoneId::Graveyard(player.id),
+        ]);
+    }
+
+    let expected: BTreeSet<_> = zone_ids.into_iter().collect();
+    let actual: BTreeSet<_> = game.zones.keys().copied().collect();
+    if actual != expected {
+        return Err("the game's zones don't match its players".to_owned());
+    }
+
+    for (zone_id, zone) in &game.zones {
+        for &entity in zone.members() {
+            match game.world.get::<Object>(entity) {
+                Ok(object) if object.zone == *zone_id => {}
+                _ => {
+                    return Err(format!(
+                        "{:?} is listed in {:?}, but isn't an object there",
+                        entity, zone_id
+                    ))
+                }
+            }
+        }
+    }
+
+    for (entity, object) in game.world.query::<&Object>().iter() {
+        let in_zone = game.zones[&object.zone].members().contains(&entity);
+        if !in_zone {
+            return Err(format!(
+                "{:?} is in {:?}, but isn't listed there",
+                entity, object.zone
+            ));
+        }
+
+        if !is_player(object.owner) || object.controller.is_some_and(|player| !is_player(player)) {
+            return Err(format!(
+                "{:?} is owned or controlled by a player who isn't in the game",
+                entity
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Perform an action that must either complete entirely or not happen at all.
+/// If it fails, the game returns to the moment before it was attempted.
+pub fn transaction<T, E>(
+    game: &mut Game,
+    action: impl FnOnce(&mut Game) -> Result<T, E>,
+) -> Result<T, E> {
+    let before = snapshot(game);
+
+    let result = action(game);
+    if result.is_err() {
+        restore(game, before);
+    }
+
+    result
+}
+
+/// Serializes each component under the name of its type.
+struct ComponentContext;
+
+impl SerializeContext for ComponentContext {
+    fn serialize_entity<S>(&mut self, entity: EntityRef<'_>, map: &mut S) -> Result<(), S::Error>
+    where
+        S: SerializeMap,
+    {
+        macro_rules! serialize_components {
+            ($($component:ident),*) => {
+                $(
+                    row::try_serialize::<$component, _, _>(&entity, stringify!($component), map)?;
+                )*
+            };
+        }
+
+        with_components!(serialize_components);
+
+        Ok(())
+    }
+}
+
+impl DeserializeContext for ComponentContext {
+    fn deserialize_entity<'de, M>(
+        &mut self,
+        mut map: M,
+        entity: &mut EntityBuilder,
+    ) -> Result<(), M::Error>
+    where
+        M: MapAccess<'de>,
+    {
+        macro_rules! deserialize_component {
+            ($key:expr, $($component:ident),*) => {
+                match $key {
+                    $(
+                        stringify!($component) => {
+                            entity.add::<$component>(map.next_value()?);
+                        }
+                    )*
+                    unknown => {
+                        return Err(de::Error::unknown_field(unknown, &[$(stringify!($component)),*]));
+                    }
+                }
+            };
+        }
+
+        while let Some(key) = map.next_key::<String>()? {
+            macro_rules! deserialize_key {
+                ($($component:ident),*) => {
+                    deserialize_component!(key.as_str(), $($component),*)
+                };
+            }
+
+            with_components!(deserialize_key);
+        }
+
+        Ok(())
+    }
+}
+
+/// Every entity in a snapshot, along with a copy of its components.
+struct SnapshotEntities(Vec<(Entity, BuiltEntityClone)>);
+
+impl Clone for SnapshotEntities {
+    fn clone(&self) -> Self {
+        // A cloned `BuiltEntityClone` loses the list of component types that
+        // hecs uses to find its archetype, so each one is rebuilt through a
+        // builder, which fills the list back in.
+        let entities = self
+            .0
+            .iter()
+            .map(|(entity, components)| {
+                let builder = EntityBuilderClone::from(components.clone());
+                (*entity, builder.build())
+            })
+            .collect();
+
+        Self(entities)
+    }
+}
+
+/// Serialized as a hecs world keyed by entity ID, so that every entity keeps
+/// its ID when the snapshot is restored.
+impl Serialize for SnapshotEntities {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut world = World::new();
+        for (entity, components) in &self.0 {
+            world.spawn_at(*entity, components);
+        }
+
+        row::serialize(&world, &mut ComponentContext, serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SnapshotEntities {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let world = row::deserialize(&mut ComponentContext, deserializer)?;
+
+        let entities = world
+            .iter()
+            .map(|entity| (entity.entity(), clone_entity(entity)))
+            .collect();
+
+        Ok(Self(entities))
+    }
+}
+
+/// Serializes zones as a list of pairs, since zone IDs can't be used as keys in
+/// formats like JSON. Zones are sorted so that saving the same game always
+/// produces the same output.
+mod zones {
+    use super::*;
+
+    pub fn serialize<S>(zones: &HashMap<ZoneId, Zone>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let mut zones: Vec<_> = zones.iter().collect();
+        zones.sort_by_key(|(zone_id, _)| **zone_id);
+
+        serializer.collect_seq(zones)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<HashMap<ZoneId, Zone>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let zones: Vec<(ZoneId, Zone)> = Deserialize::deserialize(deserializer)?;
+        Ok(zones.into_iter().collect())
+    }
+}
+
+/// Serializes the random number generator by its seed and position. The
+/// position is written as a string, since it doesn't fit in a JSON number.
+mod rng {
+    use super::*;
+
+    #[derive(Serialize, Deserialize)]
+    struct RngState {
+        seed: [u8; 32],
+        stream: u64,
+        word_pos: String,
+    }
+
+    pub fn serialize<S>(rng: &ChaCha8Rng, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let state = RngState {
+            seed: rng.get_seed(),
+            stream: rng.get_stream(),
+            word_pos: rng.get_word_pos().to_string(),
+        };
+
+        state.serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D>(deserializer: D) -> Result<ChaCha8Rng, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let state = RngState::deserialize(deserializer)?;
+        let word_pos = state.word_pos.parse().map_err(de::Error::custom)?;
+
+        let mut rng = ChaCha8Rng::from_seed(state.seed);
+        rng.set_stream(state.stream);
+        rng.set_word_pos(word_pos);
+
+        Ok(rng)
+    }
+}
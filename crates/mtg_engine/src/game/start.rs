@@ -0,0 +1,324 @@
+//! 103. Starting the Game
+
+use std::collections::BTreeSet;
+use std::fmt;
+
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::action::{ActionError, PlayerActionCategory};
+use crate::components::Object;
+use crate::object_db::CardId;
+use crate::player::PlayerId;
+use crate::zone::ZoneId;
+
+use super::{Game, GameState, Step};
+
+/// Where the players are in the mulligan process. Players who are in neither
+/// set haven't declared whether they'll take a mulligan this round yet.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Mulligans {
+    /// Players who have kept their opening hand.
+    kept: BTreeSet<PlayerId>,
+
+    /// Players who have declared that they'll take a mulligan once every
+    /// player has made a declaration this round.
+    taking: BTreeSet<PlayerId>,
+}
+
+/// Why a game couldn't be started from its config.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StartError {
+    /// The config has no players.
+    NoPlayers,
+
+    /// A player's deck contains a card that isn't in the object database.
+    UnknownCard { player: PlayerId, card: CardId },
+}
+
+pub fn start_game(game: &mut Game) -> Result<(), StartError> {
+    let players: Vec<_> = game.players.iter().map(|player| player.id).collect();
+    let config = game.config.clone();
+
+    // 103.2. [...] The players’ decks become their libraries.
+    for (&player, player_config) in players.iter().zip(&config.players) {
+        for &card in &player_config.deck {
+            game.create_card(card, ZoneId::Library(player), player)
+                .ok_or(StartError::UnknownCard { player, card })?;
+        }
+    }
+
+    ask_for_starting_player(game);
+    Ok(())
+}
+
+impl fmt::Display for StartError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NoPlayers => write!(formatter, "a game needs at least one player"),
+            Self::UnknownCard { player, card } => write!(
+                formatter,
+                "the deck of {:?} contains {:?}, which is not in the object database",
+                player, card
+            ),
+        }
+    }
+}
+
+impl std::error::Error for StartError {}
+
+/// 103.1. At the start of a game, the players determine which one of them
+///        will choose who takes the first turn. In the first game of a match
+///        (including a single-game match), the players may use any mutually
+///        agreeable method (flipping a coin, rolling dice, etc.) to do so.
+pub fn ask_for_starting_player(game: &mut Game) {
+    let chooser = game.random_player();
+
+    game.state = GameState::Player {
+        player: chooser,
+        action: PlayerActionCategory::ChooseStartingPlayer,
+    };
+}
+
+pub fn choose_starting_player(
+    game: &mut Game,
+    player: PlayerId,
+    starting_player: PlayerId,
+) -> Result<(), ActionError> {
+    if !is_acting(game, player, PlayerActionCategory::ChooseStartingPlayer) {
+        return Err(ActionError::NotChoosingStartingPlayer);
+    }
+
+    if !game.players.is_in_game(starting_player) {
+        return Err(ActionError::NoSuchPlayer {
+            player: starting_player,
+        });
+    }
+
+    game.starting_player = starting_player;
+    game.active_player = starting_player;
+
+    let players: Vec<_> = game.players.in_game().map(|player| player.id).collect();
+
+    // 103.2. After the starting player has been determined, each player
+    //        shuffles their deck so that the cards are in a random order.
+    //        [...]
+    for &player in &players {
+        game.shuffle_library(player);
+    }
+
+    // 103.3. Each player begins the game with a starting life total of 20.
+    //        Some variant games have different starting life totals.
+    //
+    // Players are created with their starting life total.
+
+    // 103.4. Each player draws a number of cards equal to their starting hand
+    //        size, which is normally seven. [...]
+    for &player in &players {
+        draw_hand(game, player);
+    }
+
+    ask_for_mulligan_decision(game);
+    Ok(())
+}
+
+/// 103.4. [...] First, the starting player declares whether they will take a
+///        mulligan. Then each other player in turn order does the same. Once
+///        each player has made a declaration, all players who decided to take
+///        mulligans do so at the same time. [...] This process is then
+///        repeated until no player takes a mulligan.
+pub fn ask_for_mulligan_decision(game: &mut Game) {
+    loop {
+        let mut undecided: Vec<_> = game
+            .players
+            .in_game()
+            .map(|player| player.id)
+            .filter(|player| {
+                !game.mulligans.kept.contains(player) && !game.mulligans.taking.contains(player)
+            })
+            .collect();
+        undecided.sort_by_key(|&player| game.turn_order_position(player));
+
+        if let Some(&player) = undecided.first() {
+            game.state = GameState::Player {
+                player,
+                action: PlayerActionCategory::Mulligan,
+            };
+            return;
+        }
+
+        if game.mulligans.taking.is_empty() {
+            start_first_turn(game);
+            return;
+        }
+
+        let taking = std::mem::take(&mut game.mulligans.taking);
+        for player in taking {
+            if game.players.is_in_game(player) {
+                take_mulligan(game, player);
+            }
+        }
+    }
+}
+
+pub fn mulligan(game: &mut Game, player: PlayerId) -> Result<(), ActionError> {
+    if !is_acting(game, player, PlayerActionCategory::Mulligan) {
+        return Err(ActionError::NotDecidingMulligan);
+    }
+
+    // A player can keep taking mulligans until their opening hand would be
+    // zero cards.
+    if cards_to_bottom(game, player) >= game.config.starting_hand_size {
+        return Err(ActionError::NoMoreMulligans);
+    }
+
+    game.mulligans.taking.insert(player);
+    ask_for_mulligan_decision(game);
+    Ok(())
+}
+
+/// 103.4. [...] To take a mulligan, a player shuffles the cards in their hand
+///        back into their library, then draws a new hand of cards equal to
+///        their starting hand size. [...]
+fn take_mulligan(game: &mut Game, player: PlayerId) {
+    log::debug!("Player {:?} is taking a mulligan", player);
+
+    let hand = game.zones[&ZoneId::Hand(player)].members().to_vec();
+    for card in hand {
+        game.move_object_to_zone(card, ZoneId::Library(player));
+    }
+
+    game.shuffle_library(player);
+    draw_hand(game, player);
+
+    if let Some(player) = game.players.get_mut(player) {
+        player.mulligans_taken += 1;
+    }
+}
+
+pub fn keep_hand(game: &mut Game, player: PlayerId, bottom: &[Entity]) -> Result<(), ActionError> {
+    if !is_acting(game, player, PlayerActionCategory::Mulligan) {
+        return Err(ActionError::NotDecidingMulligan);
+    }
+
+    let required = cards_to_bottom(game, player);
+    if bottom.len() != required {
+        return Err(ActionError::WrongBottomCount {
+            required,
+            chosen: bottom.len(),
+        });
+    }
+
+    let mut chosen = BTreeSet::new();
+    for &card in bottom {
+        if !chosen.insert(card) {
+            return Err(ActionError::BottomChosenTwice { card });
+        }
+
+        let in_hand = game
+            .world
+            .get::<Object>(card)
+            .is_ok_and(|object| object.zone == ZoneId::Hand(player));
+
+        if !in_hand {
+            return Err(ActionError::BottomNotInHand { card });
+        }
+    }
+
+    // 103.4. [...] Once a player chooses not to take a mulligan, that player
+    //        puts a number of cards from their hand on the bottom of their
+    //        library in any order equal to the number of times that player
+    //        took a mulligan. [...]
+    let library = ZoneId::Library(player);
+    for &card in bottom {
+        if game.move_object_to_zone(card, library) {
+            game.zones.get_mut(&library).unwrap().move_to_bottom(card);
+        }
+    }
+
+    game.mulligans.kept.insert(player);
+    ask_for_mulligan_decision(game);
+    Ok(())
+}
+
+/// 103.4b If an effect allows a player to perform an action “any time [that
+///        player] could mulligan,” the player may perform that action at a time
+///        they would declare whether or not they will take a mulligan. [...]
+pub fn use_mulligan_ability(
+    game: &mut Game,
+    player: PlayerId,
+    card: Entity,
+) -> Result<(), ActionError> {
+    if !is_acting(game, player, PlayerActionCategory::Mulligan) {
+        return Err(ActionError::NotDecidingMulligan);
+    }
+
+    let effects = {
+        let object = game
+            .world
+            .get::<Object>(card)
+            .map_err(|_| ActionError::NoSuchObject { object: card })?;
+
+        if object.zone != ZoneId::Hand(player) {
+            return Err(ActionError::MulliganAbilityNotInHand { card });
+        }
+
+        if object.mulligan_abilities.is_empty() {
+            return Err(ActionError::NoMulliganAbility { card });
+        }
+
+        object
+            .mulligan_abilities
+            .iter()
+            .flat_map(|ability| ability.effects.iter().cloned())
+            .collect::<Vec<_>>()
+    };
+
+    // [...] If the player performs the action, they then declare whether they
+    //       will take a mulligan.
+    super::effects::apply(game, player, &effects);
+    Ok(())
+}
+
+/// The number of cards a player will put on the bottom of their library when
+/// they keep their current hand.
+pub fn cards_to_bottom(game: &Game, player: PlayerId) -> usize {
+    let taken = game
+        .players
+        .get(player)
+        .map_or(0, |player| player.mulligans_taken as usize);
+
+    // 103.4c In a multiplayer game and in any Brawl game, the first mulligan a
+    //        player takes doesn’t count toward the number of cards that player
+    //        will put on the bottom of their library. [...]
+    if game.config.free_first_mulligan {
+        taken.saturating_sub(1)
+    } else {
+        taken
+    }
+}
+
+fn draw_hand(game: &mut Game, player: PlayerId) {
+    for _ in 0..game.config.starting_hand_size {
+        game.draw_card(player);
+    }
+}
+
+fn start_first_turn(game: &mut Game) {
+    // 103.5. Some cards allow a player to take actions with them from their
+    //        opening hand. [...]
+    //
+    // TODO: No cards with actions like these exist yet.
+
+    // 103.7. The starting player takes their first turn.
+    game.turn_number = 1;
+    game.active_player = game.starting_player;
+    game.enter_step(Step::Untap);
+}
+
+fn is_acting(game: &Game, player: PlayerId, category: PlayerActionCategory) -> bool {
+    matches!(
+        &game.state,
+        GameState::Player { player: acting, action } if *acting == player && *action == category
+    )
+}
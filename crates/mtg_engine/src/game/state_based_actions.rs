@@ -1,6 +1,9 @@
 use std::collections::HashSet;
 
-use crate::components::AttachedToEntity;
+use crate::components::{AttachedToEntity, Damage, Object, Permanent};
+use crate::queries::QueryPt;
+use crate::types::CardType;
+use crate::zone::ZoneId;
 
 use super::Game;
 
@@ -68,18 +71,61 @@ pub fn apply(game: &mut Game) -> bool {
     //
     // TODO
 
+    // Objects with an event waiting on a choice of replacement effect, like a
+    // creature whose destruction became a zone change that two effects want
+    // to replace. The event will still happen once the choice is made, so
+    // they're left alone until then.
+    let pending: HashSet<_> = game
+        .pending_replacements
+        .iter()
+        .filter_map(|pending| pending.event.affected_object())
+        .collect();
+
+    let mut zero_toughness = Vec::new();
+    let mut lethal_damage = Vec::new();
+    {
+        let mut query = game.world.query::<(&Object, &Permanent)>();
+
+        for (entity, (object, _permanent)) in query.iter() {
+            if !object.types.contains(&CardType::Creature) || pending.contains(&entity) {
+                continue;
+            }
+
+            let pt = match game.query(QueryPt(entity)) {
+                Some(pt) => pt,
+                None => continue,
+            };
+
+            let damage = game
+                .world
+                .get::<Damage>(entity)
+                .map(|damage| damage.amount)
+                .unwrap_or(0);
+
+            if pt.toughness <= 0 {
+                zero_toughness.push((entity, object.owner));
+            } else if damage > 0 && damage as i64 >= pt.toughness {
+                lethal_damage.push(entity);
+            }
+        }
+    }
+
     // 704.5f If a creature has toughness 0 or less, it’s put into its
     //        owner’s graveyard. Regeneration can’t replace this event.
-    //
-    // TODO
+    for (creature, owner) in zero_toughness {
+        game.move_object_to_zone(creature, ZoneId::Graveyard(owner));
+        actions_performed = true;
+    }
 
     // 704.5g If a creature has toughness greater than 0, it has damage
     //        marked on it, and the total damage marked on it is greater
     //        than or equal to its toughness, that creature has been dealt
     //        lethal damage and is destroyed. Regeneration can replace this
     //        event.
-    //
-    // TODO
+    for creature in lethal_damage {
+        super::damage::destroy(game, creature);
+        actions_performed = true;
+    }
 
     // 704.5h If a creature has toughness greater than 0, and it’s been
     //        dealt damage by a source with deathtouch since the last time
@@ -148,8 +194,14 @@ pub fn apply(game: &mut Game) -> bool {
     //        source of a chapter ability that has triggered but not yet
     //        left the stack, that Saga’s controller sacrifices it. See rule
     //        714, “Saga Cards.”
-    //
-    // TODO
+    for saga in super::sagas::sagas_to_sacrifice(game) {
+        if pending.contains(&saga) {
+            continue;
+        }
+
+        game.sacrifice(saga);
+        actions_performed = true;
+    }
 
     if !losers.is_empty() {
         let losers: Vec<_> = losers.into_iter().collect();
@@ -0,0 +1,77 @@
+//! 603. Handling Triggered Abilities
+
+use crate::components::{Object, TriggeredAbility};
+use crate::zone::ZoneId;
+
+use super::Game;
+
+/// 603.3. Once an ability has triggered, its controller puts it on the stack
+///        as an object that’s not a card the next time a player would receive
+///        priority. See rule 117, “Timing and Priority.”
+///
+/// 603.3b If multiple players have triggered abilities that have triggered
+///        since the last time a player received priority, each player, in
+///        APNAP order, puts triggered abilities they control on the stack in
+///        any order they choose. (See rule 101.4.)
+///
+/// Returns whether any abilities were put onto the stack.
+pub fn put_pending_triggers_on_stack(game: &mut Game) -> bool {
+    if game.pending_triggers.is_empty() {
+        return false;
+    }
+
+    let mut pending = std::mem::take(&mut game.pending_triggers);
+
+    // TODO: Let each player choose the order of the triggers they control
+    // instead of using the order that they triggered in.
+    let mut player = game.active_player;
+    for _ in 0..game.players.len() {
+        let (controlled, rest) = pending
+            .into_iter()
+            .partition(|trigger| trigger.controller == player);
+        pending = rest;
+
+        for trigger in controlled {
+            put_on_stack(game, trigger);
+        }
+
+        player = game.players.player_after(player);
+    }
+
+    true
+}
+
+fn put_on_stack(game: &mut Game, trigger: TriggeredAbility) {
+    let name = match game.world.get::<Object>(trigger.source) {
+        Ok(source) => source.name.clone(),
+        Err(_) => {
+            log::warn!(
+                "Source {:?} of triggered ability no longer exists",
+                trigger.source
+            );
+            return;
+        }
+    };
+
+    log::debug!("Putting triggered ability of {:?} on the stack", name);
+
+    let controller = trigger.controller;
+    let ability = game.world.spawn((
+        Object {
+            name,
+            types: Vec::new(),
+            supertypes: Vec::new(),
+            subtypes: Vec::new(),
+            pt: None,
+            mana_cost: None,
+            chapter_abilities: Vec::new(),
+            mulligan_abilities: Vec::new(),
+            zone: ZoneId::Stack,
+            owner: controller,
+            controller: Some(controller),
+        },
+        trigger,
+    ));
+
+    game.zones.get_mut(&ZoneId::Stack).unwrap().add(ability);
+}
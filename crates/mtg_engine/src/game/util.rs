@@ -1,23 +1,52 @@
 use crate::action::{PlayerAction, PlayerActionCategory};
+use crate::zone::ZoneId;
 
 use super::{Game, GameState};
 
 /// Advance the game with no players taking any actions.
 pub fn advance_with_no_actions(game: &mut Game) {
     let state = game.state.clone();
-    match state {
+    let result = match state {
         GameState::Player { player, action } => match action {
             PlayerActionCategory::Priority => game.do_action(player, PlayerAction::PassPriority),
+            PlayerActionCategory::ChooseStartingPlayer => {
+                game.do_action(player, PlayerAction::ChooseStartingPlayer { player })
+            }
+            PlayerActionCategory::Mulligan => {
+                // Keeping is the closest thing to doing nothing, so put the
+                // oldest cards on the bottom.
+                let count = super::start::cards_to_bottom(game, player);
+                let hand = game.zone(ZoneId::Hand(player)).unwrap().members();
+                let bottom = hand[..count].to_vec();
+
+                game.do_action(player, PlayerAction::KeepHand { bottom })
+            }
             PlayerActionCategory::ChooseAttackers => {
                 game.do_action(player, PlayerAction::ChooseAttackers { attackers: vec![] })
             }
             PlayerActionCategory::ChooseBlockers => {
                 game.do_action(player, PlayerAction::ChooseBlockers { blockers: vec![] })
             }
+            PlayerActionCategory::DiscardToHandSize => {
+                // Discarding isn't optional, so choose the oldest cards.
+                let count = super::cleanup::cards_to_discard(game, player);
+                let hand = game.zone(ZoneId::Hand(player)).unwrap().members();
+                let cards = hand[..count].to_vec();
+
+                game.do_action(player, PlayerAction::DiscardToHandSize { cards })
+            }
+            PlayerActionCategory::ChooseReplacementEffect { options } => game.do_action(
+                player,
+                PlayerAction::ChooseReplacementEffect { effect: options[0] },
+            ),
             _ => {
                 panic!("cannot advance without doing nothing: {:?}", game)
             }
         },
-        GameState::Complete(_) => {}
+        GameState::Complete(_) => Ok(()),
+    };
+
+    if let Err(err) = result {
+        panic!("could not advance the game: {}", err);
     }
 }
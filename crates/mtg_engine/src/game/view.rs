@@ -0,0 +1,153 @@
+//! 400.2. Public and hidden zones. A zone is a public zone if all players can
+//!        see the cards’ faces, except for those cards that some rule or effect
+//!        specifically allow to be face down. Graveyard, battlefield, stack,
+//!        exile, ante, and command are public zones. Hidden zones are zones in
+//!        which not all players can be expected to see the cards’ faces.
+//!        Library and hand are hidden zones, even if all the cards in one such
+//!        zone happen to be revealed.
+
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::components::{Card, Counters, Damage, IncompleteSpell, Object, Permanent, Revealed};
+use crate::player::{Player, PlayerId};
+use crate::zone::ZoneId;
+
+use super::{Game, GameState, Step};
+
+/// Who a [`PlayerView`] is being shown to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum Viewer {
+    Player {
+        player: PlayerId,
+    },
+
+    /// Someone watching the game who can only see public information.
+    Spectator,
+}
+
+impl Viewer {
+    fn player(&self) -> Option<PlayerId> {
+        match self {
+            Self::Player { player } => Some(*player),
+            Self::Spectator => None,
+        }
+    }
+}
+
+/// Everything about a game that a player or spectator is allowed to know.
+/// Cards in hidden zones are left out unless the viewer can see them, and the
+/// order of libraries is never shown.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayerView {
+    pub viewer: Viewer,
+    pub turn_number: u64,
+    pub active_player: PlayerId,
+    pub step: Step,
+    pub state: GameState,
+    pub players: Vec<Player>,
+    pub zones: Vec<ZoneView>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZoneView {
+    pub zone: ZoneId,
+
+    /// How many objects are in the zone, including ones the viewer can't see.
+    ///
+    /// 402.3. [...] A player can’t look at the cards in another player’s hand
+    ///        but can count those cards at any time.
+    pub size: usize,
+
+    /// The objects in the zone that the viewer can see. For libraries, these
+    /// are in no particular order.
+    pub objects: Vec<ObjectView>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ObjectView {
+    pub entity: Entity,
+    pub object: Object,
+    pub card: Option<Card>,
+    pub permanent: Option<Permanent>,
+    pub incomplete_spell: Option<IncompleteSpell>,
+    pub counters: Option<Counters>,
+    pub damage: Option<Damage>,
+}
+
+pub fn view(game: &Game, viewer: Viewer) -> PlayerView {
+    let mut zone_ids: Vec<_> = game.zones.keys().copied().collect();
+    zone_ids.sort();
+
+    let zones = zone_ids
+        .into_iter()
+        .map(|zone| zone_view(game, viewer, zone))
+        .collect();
+
+    PlayerView {
+        viewer,
+        turn_number: game.turn_number,
+        active_player: game.active_player,
+        step: game.step,
+        state: game.state.clone(),
+        players: game.players.iter().cloned().collect(),
+        zones,
+    }
+}
+
+fn zone_view(game: &Game, viewer: Viewer, zone: ZoneId) -> ZoneView {
+    let members = game.zones[&zone].members();
+
+    let mut objects: Vec<_> = members
+        .iter()
+        .filter(|&&entity| can_see(game, viewer, zone, entity))
+        .filter_map(|&entity| object_view(game, entity))
+        .collect();
+
+    // 401.2. Each library must be kept in a single face-down pile. Players
+    //        can’t look at or change the order of cards in a library.
+    if matches!(zone, ZoneId::Library(_)) {
+        objects.sort_by_key(|object| object.entity);
+    }
+
+    ZoneView {
+        zone,
+        size: members.len(),
+        objects,
+    }
+}
+
+pub(super) fn can_see(game: &Game, viewer: Viewer, zone: ZoneId, entity: Entity) -> bool {
+    let hidden_zone_owner = match zone {
+        ZoneId::Hand(owner) => Some(owner),
+        ZoneId::Library(_) => None,
+        _ => return true,
+    };
+
+    // 402.3. A player may arrange their hand in any convenient fashion and
+    //        look at it as much as they want.
+    if hidden_zone_owner.is_some() && hidden_zone_owner == viewer.player() {
+        return true;
+    }
+
+    match game.world.get::<Revealed>(entity) {
+        Ok(revealed) => revealed.is_visible_to(viewer.player()),
+        Err(_) => false,
+    }
+}
+
+fn object_view(game: &Game, entity: Entity) -> Option<ObjectView> {
+    let entity_ref = game.world.entity(entity).ok()?;
+    let object = entity_ref.get::<Object>()?;
+
+    Some(ObjectView {
+        entity,
+        object: (*object).clone(),
+        card: entity_ref.get::<Card>().as_deref().cloned(),
+        permanent: entity_ref.get::<Permanent>().as_deref().cloned(),
+        incomplete_spell: entity_ref.get::<IncompleteSpell>().as_deref().cloned(),
+        counters: entity_ref.get::<Counters>().as_deref().cloned(),
+        damage: entity_ref.get::<Damage>().as_deref().cloned(),
+    })
+}
@@ -0,0 +1,35 @@
+//! Contains effects that change a player's maximum hand size.
+
+use serde::{Deserialize, Serialize};
+
+use crate::player::PlayerId;
+
+/// 402.2. Each player has a maximum hand size, which is normally seven cards.
+///        A player may have any number of cards in their hand, but as part of
+///        their cleanup step, the player must discard excess cards down to
+///        the maximum hand size.
+pub const DEFAULT_MAX_HAND_SIZE: i64 = 7;
+
+/// An effect that changes the maximum hand size of a player, like Spellbook,
+/// Cursed Rack, or Thought Vessel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaxHandSizeEffect {
+    // TODO: Change to selector type
+    pub player: PlayerId,
+
+    pub modification: MaxHandSizeModification,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MaxHandSizeModification {
+    /// The player has no maximum hand size, like Spellbook or Reliquary Tower.
+    NoMaximum,
+
+    /// The player's maximum hand size becomes a specific number, like Cursed
+    /// Rack.
+    Set(i64),
+
+    /// The player's maximum hand size is increased or reduced, like Gnat
+    /// Miser or the hand modifier of a vanguard card.
+    Adjust(i64),
+}
@@ -1,3 +1,5 @@
+use serde::{Deserialize, Serialize};
+
 /// 702. Keyword Abilities
 ///
 /// 702.1. Most abilities describe exactly what they do in the card’s rules
@@ -5,5 +7,5 @@
 ///        to define on the card. In these cases, the object lists only the name
 ///        of the ability as a “keyword”; sometimes reminder text summarizes the
 ///        game rule.
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct KeywordAbility;
@@ -0,0 +1,318 @@
+//! Finding a way to pay a mana cost with the mana a player has.
+//!
+//! Each mana can only pay for some items of a cost, so paying mana toward the
+//! wrong item can leave another item unpayable even when the same mana could
+//! have paid for everything. The solver treats the payment as a flow of mana
+//! into the places in the cost that it can fill, so it finds a payment
+//! whenever one exists, in time polynomial in the amount of mana and the size
+//! of the cost.
+//!
+//! The solver doesn't check restrictions on what mana can be spent on, so
+//! only mana that can be spent on the cost should be given to it.
+//!
+//! TODO: Activating the mana abilities of untapped permanents as part of a
+//! payment, once permanents have mana abilities.
+
+use std::collections::BTreeMap;
+
+use crate::cost::{ManaColor, ManaCost, ManaCostItem};
+use crate::mana_pool::{Mana, ManaId, ManaPool};
+
+/// Mana that the solver can spend on a cost.
+#[derive(Debug, Clone)]
+pub struct AvailableMana {
+    pub id: ManaId,
+    pub mana: Mana,
+
+    /// Whether the payment has to spend this mana, like mana that a player
+    /// already chose to pay with.
+    pub required: bool,
+}
+
+/// Find a way to pay for every item of a mana cost with mana from a pool. The
+/// result has the mana to spend on each item of the cost, in the same order,
+/// or is `None` if the pool doesn't have the mana to pay for the cost.
+///
+/// Phyrexian mana symbols are only paid for with mana, and restrictions on
+/// what the mana can be spent on are ignored.
+pub fn find_payment(cost: &ManaCost, pool: &ManaPool) -> Option<Vec<Vec<ManaId>>> {
+    let mana: Vec<_> = pool
+        .iter()
+        .map(|(id, mana)| AvailableMana {
+            id,
+            mana: mana.clone(),
+            required: false,
+        })
+        .collect();
+
+    solve(&cost.items, &mana, true)
+}
+
+/// Assign mana to the items of a cost, spending every required mana. If
+/// `pay_in_full` is true, every item must be paid for, and mana that isn't
+/// required is spent only where it's needed. Otherwise, items can be left
+/// partly or entirely unpaid.
+///
+/// The result has the mana to spend on each item, in the same order as
+/// `items`, or is `None` if no assignment meets those conditions.
+pub fn solve(
+    items: &[ManaCostItem],
+    mana: &[AvailableMana],
+    pay_in_full: bool,
+) -> Option<Vec<Vec<ManaId>>> {
+    // Each item needs at least one mana of its own.
+    if pay_in_full && items.len() > mana.len() {
+        return None;
+    }
+
+    // 107.4e [...] a monocolored hybrid symbol such as {2/B} can be paid
+    //        with either one black mana or two mana of any type.
+    //
+    // Only the number of symbols of each color paid with one mana matters,
+    // so each number is tried, from the most paid with one mana to the
+    // fewest. Partial payments don't have to fill any slots, so they can
+    // always leave room for two mana.
+    let mut hybrid_counts: BTreeMap<ManaColor, usize> = BTreeMap::new();
+    if pay_in_full {
+        for item in items {
+            if let ManaCostItem::MonocoloredHybridMana { color } = item {
+                *hybrid_counts.entry(*color).or_default() += 1;
+            }
+        }
+    }
+
+    let mut single: BTreeMap<ManaColor, usize> = hybrid_counts.clone();
+    loop {
+        if let Some(payment) = solve_with(items, mana, pay_in_full, &single) {
+            return Some(payment);
+        }
+
+        // Count down like an odometer, stopping once every count has wrapped.
+        let next = single.iter_mut().find(|(_, count)| **count > 0)?;
+        *next.1 -= 1;
+        let decremented = *next.0;
+        for (color, count) in single.iter_mut() {
+            if *color == decremented {
+                break;
+            }
+            *count = hybrid_counts[color];
+        }
+    }
+}
+
+/// Assign mana like [`solve`], with the given number of monocolored hybrid
+/// symbols of each color paid with one mana of that color, and the rest with
+/// two mana.
+fn solve_with(
+    items: &[ManaCostItem],
+    mana: &[AvailableMana],
+    pay_in_full: bool,
+    single: &BTreeMap<ManaColor, usize>,
+) -> Option<Vec<Vec<ManaId>>> {
+    let mut single = single.clone();
+    let mut slots = Vec::new();
+
+    for (index, item) in items.iter().enumerate() {
+        match *item {
+            ManaCostItem::MonocoloredHybridMana { color } => {
+                let one_mana = single.get_mut(&color).filter(|count| **count > 0);
+
+                // The mana that isn't the symbol's color goes first, so that
+                // the mana of its color doesn't pay for the symbol on its own
+                // before the other is added.
+                match one_mana {
+                    Some(count) => {
+                        *count -= 1;
+                        slots.push(Slot::new(index, Fits::NotColor(color), false));
+                        slots.push(Slot::new(index, Fits::Color(color), true));
+                    }
+                    None => {
+                        slots.push(Slot::new(index, Fits::NotColor(color), pay_in_full));
+                        slots.push(Slot::new(index, Fits::Any, pay_in_full));
+                    }
+                }
+            }
+            _ => slots.push(Slot::new(index, Fits::Item(*item), pay_in_full)),
+        }
+    }
+
+    // 0 and 1 are the source and sink of the payment, and 2 and 3 are the
+    // source and sink of the mana and slots that have to be filled.
+    let mana_node = |index: usize| 4 + index;
+    let slot_node = |index: usize| 4 + mana.len() + index;
+    let mut flow = Flow::new(4 + mana.len() + slots.len());
+
+    let mut needed = 0;
+    for (index, available) in mana.iter().enumerate() {
+        if available.required {
+            flow.add_edge(2, mana_node(index), 1);
+            flow.add_edge(0, 3, 1);
+            needed += 1;
+        } else {
+            flow.add_edge(0, mana_node(index), 1);
+        }
+    }
+
+    let mut placements = Vec::new();
+    for (index, available) in mana.iter().enumerate() {
+        for (slot_index, slot) in slots.iter().enumerate() {
+            // Optional mana only goes where it's needed.
+            if !available.required && !slot.required {
+                continue;
+            }
+
+            if slot.fits(&available.mana) {
+                let edge = flow.add_edge(mana_node(index), slot_node(slot_index), 1);
+                placements.push((edge, index, slot_index));
+            }
+        }
+    }
+
+    for (index, slot) in slots.iter().enumerate() {
+        if slot.required {
+            flow.add_edge(2, 1, 1);
+            flow.add_edge(slot_node(index), 3, 1);
+            needed += 1;
+        } else {
+            flow.add_edge(slot_node(index), 1, 1);
+        }
+    }
+    flow.add_edge(1, 0, mana.len());
+
+    if flow.max_flow(2, 3) < needed {
+        return None;
+    }
+
+    let mut filled: Vec<_> = placements
+        .into_iter()
+        .filter(|&(edge, _, _)| flow.is_used(edge))
+        .map(|(_, index, slot)| (slot, index))
+        .collect();
+    filled.sort_unstable();
+
+    let mut payment = vec![Vec::new(); items.len()];
+    for (slot, index) in filled {
+        payment[slots[slot].item].push(mana[index].id);
+    }
+
+    Some(payment)
+}
+
+/// A place in the cost that one mana can be spent on.
+struct Slot {
+    /// The index of the item that the mana goes toward.
+    item: usize,
+    fits: Fits,
+
+    /// Whether the slot has to be filled.
+    required: bool,
+}
+
+impl Slot {
+    fn new(item: usize, fits: Fits, required: bool) -> Self {
+        Self {
+            item,
+            fits,
+            required,
+        }
+    }
+
+    fn fits(&self, mana: &Mana) -> bool {
+        match self.fits {
+            Fits::Item(item) => item.can_be_paid_with(mana),
+            Fits::Color(color) => mana.color == Some(color),
+            Fits::NotColor(color) => mana.color != Some(color),
+            Fits::Any => true,
+        }
+    }
+}
+
+/// Which mana can fill a slot.
+enum Fits {
+    /// Mana that pays for the item on its own.
+    Item(ManaCostItem),
+    Color(ManaColor),
+    NotColor(ManaColor),
+    Any,
+}
+
+/// A flow network with integer capacities, used to find how much mana can be
+/// moved into the slots of a cost.
+struct Flow {
+    /// The edges leaving each node, as indices into `edges`.
+    nodes: Vec<Vec<usize>>,
+
+    /// Each edge is stored next to its reverse, so the reverse of edge `i` is
+    /// edge `i ^ 1`.
+    edges: Vec<Edge>,
+}
+
+struct Edge {
+    to: usize,
+    capacity: usize,
+}
+
+impl Flow {
+    fn new(nodes: usize) -> Self {
+        Self {
+            nodes: vec![Vec::new(); nodes],
+            edges: Vec::new(),
+        }
+    }
+
+    /// Add an edge, returning its index.
+    fn add_edge(&mut self, from: usize, to: usize, capacity: usize) -> usize {
+        let index = self.edges.len();
+        self.edges.push(Edge { to, capacity });
+        self.edges.push(Edge {
+            to: from,
+            capacity: 0,
+        });
+        self.nodes[from].push(index);
+        self.nodes[to].push(index + 1);
+        index
+    }
+
+    /// Whether any flow goes along the edge.
+    fn is_used(&self, edge: usize) -> bool {
+        self.edges[edge ^ 1].capacity > 0
+    }
+
+    /// Push as much flow as possible from `source` to `sink`, one path at a
+    /// time, returning the amount pushed.
+    fn max_flow(&mut self, source: usize, sink: usize) -> usize {
+        let mut total = 0;
+        loop {
+            let mut visited = vec![false; self.nodes.len()];
+            if !self.augment(source, sink, &mut visited) {
+                return total;
+            }
+            total += 1;
+        }
+    }
+
+    /// Push one unit of flow along a path from `node` to `sink`, returning
+    /// whether a path was found.
+    fn augment(&mut self, node: usize, sink: usize, visited: &mut [bool]) -> bool {
+        if node == sink {
+            return true;
+        }
+        visited[node] = true;
+
+        for position in 0..self.nodes[node].len() {
+            let edge = self.nodes[node][position];
+            let Edge { to, capacity } = self.edges[edge];
+            if capacity == 0 || visited[to] {
+                continue;
+            }
+
+            if self.augment(to, sink, visited) {
+                self.edges[edge].capacity -= 1;
+                self.edges[edge ^ 1].capacity += 1;
+                return true;
+            }
+        }
+
+        false
+    }
+}
@@ -1,24 +1,59 @@
-use serde::{Deserialize, Serialize};
+use hecs::Entity;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use thunderdome::{Arena, Index};
 
-use crate::cost::ManaColor;
+use crate::components::Object;
+use crate::cost::{ManaColor, ManaCostItem};
+use crate::player::PlayerId;
+use crate::types::CardType;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
-#[serde(transparent)]
-pub struct ManaId(u32);
+/// Identifies one mana in a player's mana pool. The ID stays the same until
+/// the mana leaves the pool, and isn't reused for other mana afterward.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ManaId(Index);
 
+impl Serialize for ManaId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.0.to_bits().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for ManaId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let bits = u64::deserialize(deserializer)?;
+        let index = Index::from_bits(bits)
+            .ok_or_else(|| serde::de::Error::custom(format!("{} is not a mana ID", bits)))?;
+
+        Ok(ManaId(index))
+    }
+}
+
+/// 106.4. When an effect instructs a player to add mana, that mana goes into
+///        a player’s mana pool. From there, it can be used to pay costs
+///        immediately, or it can stay in the player’s mana pool as
+///        unspent mana. [...]
 #[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(transparent)]
+#[serde(into = "Vec<PooledMana>", from = "Vec<PooledMana>")]
 pub struct ManaPool {
-    manas: Vec<Mana>,
+    manas: Arena<Mana>,
+}
+
+/// How mana in a pool is saved, keeping its ID.
+#[derive(Serialize, Deserialize)]
+struct PooledMana {
+    id: ManaId,
+    mana: Mana,
 }
 
 impl ManaPool {
     pub fn new() -> Self {
-        Self { manas: Vec::new() }
+        Self {
+            manas: Arena::new(),
+        }
     }
 
-    pub fn get(&self, id: ManaId) -> Option<Mana> {
-        self.manas.get(id.0 as usize).cloned()
+    pub fn get(&self, id: ManaId) -> Option<&Mana> {
+        self.manas.get(id.0)
     }
 
     pub fn clear(&mut self) {
@@ -29,27 +64,179 @@ impl ManaPool {
         self.manas.is_empty()
     }
 
-    pub fn add(&mut self, mana: Mana) {
-        self.manas.push(mana);
+    pub fn add(&mut self, mana: Mana) -> ManaId {
+        ManaId(self.manas.insert(mana))
     }
 
     pub fn spend(&mut self, manas: &[ManaId]) {
-        let mut index = 0;
-        self.manas.retain(|_| {
-            index += 1;
-            !manas.contains(&ManaId(index - 1))
-        });
+        for id in manas {
+            self.manas.remove(id.0);
+        }
     }
 
-    pub fn iter(&self) -> impl Iterator<Item = (ManaId, Mana)> + '_ {
-        self.manas
-            .iter()
-            .enumerate()
-            .map(|(index, mana)| (ManaId(index as u32), *mana))
+    /// Keep only the mana for which `keep` returns true. Mana that's kept
+    /// can be changed, and keeps its ID.
+    pub fn retain(&mut self, mut keep: impl FnMut(ManaId, &mut Mana) -> bool) {
+        self.manas.retain(|index, mana| keep(ManaId(index), mana));
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (ManaId, &Mana)> + '_ {
+        self.manas.iter().map(|(index, mana)| (ManaId(index), mana))
+    }
+}
+
+impl Default for ManaPool {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<ManaPool> for Vec<PooledMana> {
+    fn from(pool: ManaPool) -> Self {
+        pool.manas
+            .into_iter()
+            .map(|(index, mana)| PooledMana {
+                id: ManaId(index),
+                mana,
+            })
+            .collect()
+    }
+}
+
+impl From<Vec<PooledMana>> for ManaPool {
+    fn from(manas: Vec<PooledMana>) -> Self {
+        let mut pool = ManaPool::new();
+        for PooledMana { id, mana } in manas {
+            pool.manas.insert_at(id.0, mana);
+        }
+
+        pool
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Mana {
-    pub color: ManaColor,
+    /// The color of the mana, or `None` if it's colorless.
+    ///
+    /// 106.1b There are six types of mana: white, blue, black, red, green, and
+    ///        colorless.
+    pub color: Option<ManaColor>,
+
+    /// Whether the mana was produced by a snow permanent, which lets it pay
+    /// for {S}. See rule 107.4h.
+    #[serde(default)]
+    pub snow: bool,
+
+    /// The object whose ability produced the mana, or `None` if it was added
+    /// some other way.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source: Option<Entity>,
+
+    /// Restrictions on what the mana can be spent on, from the ability that
+    /// produced it.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub restrictions: Vec<ManaRestriction>,
+}
+
+impl Mana {
+    pub fn colorless() -> Self {
+        Self {
+            color: None,
+            snow: false,
+            source: None,
+            restrictions: Vec::new(),
+        }
+    }
+
+    /// The same mana, produced by a snow permanent.
+    pub fn snow(self) -> Self {
+        Self { snow: true, ..self }
+    }
+
+    /// The same mana, produced by an ability of the given object.
+    pub fn produced_by(self, source: Entity) -> Self {
+        Self {
+            source: Some(source),
+            ..self
+        }
+    }
+
+    /// The same mana, with another restriction on what it can be spent on.
+    pub fn restricted(mut self, restriction: ManaRestriction) -> Self {
+        self.restrictions.push(restriction);
+        self
+    }
+
+    /// Whether every restriction on this mana allows it to be spent on the
+    /// given spell.
+    pub fn can_be_spent_on(&self, spell: &Object) -> bool {
+        self.restrictions
+            .iter()
+            .all(|restriction| restriction.allows(spell))
+    }
+}
+
+impl From<ManaColor> for Mana {
+    fn from(color: ManaColor) -> Self {
+        Self {
+            color: Some(color),
+            ..Mana::colorless()
+        }
+    }
+}
+
+/// 106.6. Some spells or abilities that produce mana restrict how that mana
+///        can be spent, have an additional effect that affects the spell or
+///        ability that mana is spent on, or create a delayed triggered
+///        ability (see rule 603.7a) that triggers when that mana is spent.
+///        This doesn’t affect the mana’s type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ManaRestriction {
+    /// "Spend this mana only to cast [card type] spells."
+    OnlySpellType { card_type: CardType },
+
+    /// "This mana can't be spent to cast [card type] spells."
+    NotSpellType { card_type: CardType },
+
+    /// "Spend this mana only on costs that contain {X}."
+    OnlyCostsWithX,
+}
+
+impl ManaRestriction {
+    /// Whether the restriction allows mana to be spent on the given spell.
+    pub fn allows(&self, spell: &Object) -> bool {
+        match self {
+            ManaRestriction::OnlySpellType { card_type } => spell.types.contains(card_type),
+            ManaRestriction::NotSpellType { card_type } => !spell.types.contains(card_type),
+            ManaRestriction::OnlyCostsWithX => spell
+                .mana_cost
+                .as_ref()
+                .is_some_and(|cost| cost.items.contains(&ManaCostItem::XGenericMana)),
+        }
+    }
+}
+
+/// An effect that changes what happens to a player's unspent mana as steps
+/// and phases end, like Upwelling or Kruphix, God of Horizons.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnspentManaEffect {
+    // TODO: Change to selector type
+    pub player: PlayerId,
+
+    pub modification: UnspentManaModification,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum UnspentManaModification {
+    /// The player's mana pool doesn't empty, like Upwelling.
+    Keep,
+
+    /// Mana of one color doesn't empty from the player's mana pool, like
+    /// Omnath, Locus of Mana.
+    KeepColor(ManaColor),
+
+    /// Mana that would empty from the player's mana pool becomes colorless
+    /// instead, like Kruphix, God of Horizons or Horizon Stone.
+    BecomeColorless,
 }
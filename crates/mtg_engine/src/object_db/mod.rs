@@ -1,34 +1,144 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::Path;
 
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 
+use crate::ability::{ChapterAbility, MulliganAbility};
 use crate::card::CardDescriptor;
+use crate::cost::ManaCost;
 use crate::ident::Ident;
+use crate::pt::PtCharacteristic;
+use crate::types::{CardSubtype, CardSupertype, CardType};
+
+pub mod scryfall;
 
 static CARDS_JSON: &str = include_str!("./cards.json");
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct CardId(pub u32);
 
-#[derive(Clone)]
+#[derive(Clone, Default)]
 pub struct ObjectDb {
     cards: Vec<CardDescriptor>,
     card_name_to_index: HashMap<Ident, usize>,
 }
 
+/// The reason that card data couldn't be loaded into an [`ObjectDb`].
+#[derive(Debug)]
+pub enum ObjectDbError {
+    /// The card data couldn't be read.
+    Io(io::Error),
+
+    /// The card data isn't a JSON list.
+    Json(serde_json::Error),
+
+    /// Some of the cards in the list are invalid. Every invalid card is
+    /// reported, not just the first.
+    Cards(Vec<CardError>),
+}
+
+/// A problem with one card in a list of card data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CardError {
+    /// The name of the card, or its position in the list if it has no name.
+    pub card: String,
+
+    /// The field of the card that's invalid, if the problem is with a single
+    /// field.
+    pub field: Option<String>,
+
+    pub message: String,
+}
+
 impl ObjectDb {
+    /// Load the cards that are built into the engine.
     pub fn load() -> Self {
-        let cards: Vec<CardDescriptor> = serde_json::from_str(CARDS_JSON).unwrap();
+        Self::from_json(CARDS_JSON).expect("the built-in card data should be valid")
+    }
+
+    /// Load cards from a JSON list of card descriptors.
+    pub fn from_json(source: &str) -> Result<Self, ObjectDbError> {
+        let values: Vec<Value> = serde_json::from_str(source).map_err(ObjectDbError::Json)?;
+        Self::from_values(values)
+    }
+
+    /// Load cards from a reader that produces a JSON list of card descriptors.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self, ObjectDbError> {
+        let values: Vec<Value> = serde_json::from_reader(reader).map_err(ObjectDbError::Json)?;
+        Self::from_values(values)
+    }
+
+    /// Load cards from a file containing a JSON list of card descriptors.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, ObjectDbError> {
+        let file = File::open(path).map_err(ObjectDbError::Io)?;
+        Self::from_reader(BufReader::new(file))
+    }
 
-        let mut card_name_to_index = HashMap::new();
-        for (index, card) in cards.iter().enumerate() {
-            card_name_to_index.insert(card.name.clone(), index);
+    /// Create a database from cards that have already been parsed.
+    pub fn from_cards(cards: Vec<CardDescriptor>) -> Result<Self, ObjectDbError> {
+        let mut db = Self::default();
+        db.add_cards(cards)?;
+        Ok(db)
+    }
+
+    fn from_values(values: Vec<Value>) -> Result<Self, ObjectDbError> {
+        let mut cards = Vec::new();
+        let mut errors = Vec::new();
+
+        for (index, value) in values.into_iter().enumerate() {
+            match CardDescriptor::deserialize(&value) {
+                Ok(card) => cards.push(card),
+                Err(err) => errors.push(card_error(index, &value, err)),
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ObjectDbError::Cards(errors));
+        }
+
+        Self::from_cards(cards)
+    }
+
+    /// Add the cards from another database, like another set. Cards that were
+    /// already in this database keep their IDs, and the new cards' IDs follow
+    /// them.
+    ///
+    /// If any of the new cards has the same name as another card, no cards are
+    /// added and every duplicate is reported.
+    pub fn merge(&mut self, other: ObjectDb) -> Result<(), ObjectDbError> {
+        self.add_cards(other.cards)
+    }
+
+    fn add_cards(&mut self, cards: Vec<CardDescriptor>) -> Result<(), ObjectDbError> {
+        let mut names = HashSet::new();
+        let mut errors = Vec::new();
+
+        for card in &cards {
+            if self.card_name_to_index.contains_key(&card.name) || !names.insert(&card.name) {
+                errors.push(CardError {
+                    card: card.name.to_string(),
+                    field: Some("name".to_owned()),
+                    message: "another card already has this name".to_owned(),
+                });
+            }
+        }
+
+        if !errors.is_empty() {
+            return Err(ObjectDbError::Cards(errors));
         }
 
-        Self {
-            cards,
-            card_name_to_index,
+        for card in cards {
+            self.card_name_to_index
+                .insert(card.name.clone(), self.cards.len());
+            self.cards.push(card);
         }
+
+        Ok(())
     }
 
     pub fn card_id(&self, name: &str) -> Option<CardId> {
@@ -45,6 +155,121 @@ impl ObjectDb {
         let id = self.card_id(name)?;
         self.card(id)
     }
+
+    /// Every card in the database, in order of their IDs.
+    pub fn cards(&self) -> impl Iterator<Item = (CardId, &CardDescriptor)> + '_ {
+        self.cards
+            .iter()
+            .enumerate()
+            .map(|(index, card)| (CardId(index as u32), card))
+    }
+
+    pub fn len(&self) -> usize {
+        self.cards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cards.is_empty()
+    }
+}
+
+/// Describe why a card couldn't be parsed, finding the field responsible if
+/// there is one.
+fn card_error(index: usize, value: &Value, err: serde_json::Error) -> CardError {
+    let object = value.as_object();
+
+    let card = object
+        .and_then(|object| object.get("name"))
+        .and_then(Value::as_str)
+        .map(str::to_owned)
+        .unwrap_or_else(|| format!("card #{}", index));
+
+    let (field, message) = match object.and_then(field_error) {
+        Some((field, message)) => (Some(field), message),
+        None => (None, err.to_string()),
+    };
+
+    CardError {
+        card,
+        field,
+        message,
+    }
+}
+
+/// Check each field of a card descriptor on its own, returning the first one
+/// that's invalid or missing.
+///
+/// Each field of [`CardDescriptor`] needs its own arm, which the tests check
+/// against the fields the descriptor deserializes.
+fn field_error(card: &Map<String, Value>) -> Option<(String, String)> {
+    fn check<T: DeserializeOwned>(value: &Value) -> Result<(), serde_json::Error> {
+        T::deserialize(value).map(|_| ())
+    }
+
+    for (field, value) in card {
+        let result = match field.as_str() {
+            "name" => check::<Ident>(value),
+            "types" => check::<Vec<CardType>>(value),
+            "supertypes" => check::<Vec<CardSupertype>>(value),
+            "subtypes" => check::<Vec<CardSubtype>>(value),
+            "mana_cost" => check::<Option<ManaCost>>(value),
+            "image" => check::<Option<String>>(value),
+            "pt" => check::<Option<PtCharacteristic>>(value),
+            "chapter_abilities" => check::<Vec<ChapterAbility>>(value),
+            "mulligan_abilities" => check::<Vec<MulliganAbility>>(value),
+            _ => continue,
+        };
+
+        if let Err(err) = result {
+            return Some((field.clone(), err.to_string()));
+        }
+    }
+
+    ["name", "types"]
+        .into_iter()
+        .find(|field| !card.contains_key(*field))
+        .map(|field| (field.to_owned(), "this field is required".to_owned()))
+}
+
+impl fmt::Display for ObjectDbError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Io(err) => write!(formatter, "could not read card data: {}", err),
+            Self::Json(err) => write!(formatter, "card data is not a list of cards: {}", err),
+            Self::Cards(errors) => {
+                write!(formatter, "invalid card data:")?;
+
+                for error in errors {
+                    write!(formatter, "\n  {}", error)?;
+                }
+
+                Ok(())
+            }
+        }
+    }
+}
+
+impl fmt::Display for CardError {
+    fn fmt(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+        match &self.field {
+            Some(field) => write!(
+                formatter,
+                "{}, field `{}`: {}",
+                self.card, field, self.message
+            ),
+            None => write!(formatter, "{}: {}", self.card, self.message),
+        }
+    }
+}
+
+impl std::error::Error for ObjectDbError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(err) => Some(err),
+            Self::Json(err) => Some(err),
+            Self::Cards(_) => None,
+        }
+    }
 }
 
 #[cfg(test)]
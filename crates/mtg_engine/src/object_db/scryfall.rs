@@ -0,0 +1,297 @@
+//! Conversion of Scryfall's bulk card data into card descriptors.
+//!
+//! Scryfall publishes every card it knows about as one large JSON file, which
+//! is described at <https://scryfall.com/docs/api/bulk-data>. The importer
+//! reads a copy of that file that has already been downloaded, so that loading
+//! cards never depends on the network.
+//!
+//! Only a card's characteristics are converted: its name, mana cost, type
+//! line, power and toughness, and image. Rules text is much harder to model,
+//! so every card with rules text is reported in
+//! [`ScryfallImport::unmodeled`], which tells how much of the card pool the
+//! engine can actually play.
+
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+use serde::de::value::{Error as ValueError, StrDeserializer};
+use serde::de::{IgnoredAny, IntoDeserializer};
+use serde::Deserialize;
+use serde_json::Value;
+
+use crate::card::CardDescriptor;
+use crate::cost::ManaCost;
+use crate::ident::Ident;
+use crate::pt::{PtCharacteristic, PtValue, StarValue};
+use crate::types::{CardSubtype, CardSupertype, CardType};
+
+use super::{CardError, ObjectDbError};
+
+/// Layouts that Scryfall lists alongside cards, but aren't cards that can be
+/// put in a deck.
+const SKIPPED_LAYOUTS: &[&str] = &["token", "double_faced_token", "emblem", "art_series"];
+
+/// The cards read from a Scryfall bulk data file.
+#[derive(Debug, Clone)]
+pub struct ScryfallImport {
+    /// Every card whose characteristics could be converted, in the order they
+    /// appear in the file. A card that was printed more than once is only
+    /// included once.
+    pub cards: Vec<CardDescriptor>,
+
+    /// Cards that were converted, but have rules text that the engine doesn't
+    /// model. They are in `cards`, but play as if they had no abilities.
+    pub unmodeled: Vec<UnmodeledCard>,
+
+    /// Cards that couldn't be converted at all, and aren't in `cards`.
+    pub errors: Vec<CardError>,
+}
+
+/// A card that was imported without its abilities.
+#[derive(Debug, Clone, PartialEq)]
+pub struct UnmodeledCard {
+    pub name: Ident,
+
+    /// The card's rules text, as printed in Scryfall's oracle text.
+    pub text: String,
+}
+
+/// The parts of a Scryfall card object that the importer uses. See
+/// <https://scryfall.com/docs/api/cards>.
+#[derive(Deserialize)]
+struct ScryfallCard {
+    name: String,
+    lang: Option<String>,
+    layout: Option<String>,
+    mana_cost: Option<String>,
+    type_line: Option<String>,
+    oracle_text: Option<String>,
+    power: Option<String>,
+    toughness: Option<String>,
+    image_uris: Option<ImageUris>,
+    card_faces: Option<IgnoredAny>,
+}
+
+#[derive(Deserialize)]
+struct ImageUris {
+    normal: Option<String>,
+}
+
+/// Import cards from a reader that produces a Scryfall bulk data file.
+pub fn import<R: Read>(reader: R) -> Result<ScryfallImport, ObjectDbError> {
+    let values: Vec<Value> = serde_json::from_reader(reader).map_err(ObjectDbError::Json)?;
+
+    let mut output = ScryfallImport {
+        cards: Vec::new(),
+        unmodeled: Vec::new(),
+        errors: Vec::new(),
+    };
+    let mut names = HashSet::new();
+
+    for (index, value) in values.into_iter().enumerate() {
+        let card = match ScryfallCard::deserialize(&value) {
+            Ok(card) => card,
+            Err(err) => {
+                let card = value
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(str::to_owned)
+                    .unwrap_or_else(|| format!("card #{}", index));
+
+                output.errors.push(CardError {
+                    card,
+                    field: None,
+                    message: err.to_string(),
+                });
+                continue;
+            }
+        };
+
+        let skipped_layout = card
+            .layout
+            .as_deref()
+            .is_some_and(|layout| SKIPPED_LAYOUTS.contains(&layout));
+        let english = card.lang.as_deref().is_none_or(|lang| lang == "en");
+
+        // Reprints and translations are the same card as far as the rules are
+        // concerned, so only the first printing is kept.
+        if skipped_layout || !english || !names.insert(card.name.clone()) {
+            continue;
+        }
+
+        match convert(&card) {
+            Ok(descriptor) => {
+                let text = card.oracle_text.as_deref().unwrap_or("");
+                if !without_reminder_text(text).trim().is_empty() {
+                    output.unmodeled.push(UnmodeledCard {
+                        name: descriptor.name.clone(),
+                        text: text.to_owned(),
+                    });
+                }
+
+                output.cards.push(descriptor);
+            }
+            Err((field, message)) => output.errors.push(CardError {
+                card: card.name,
+                field: Some(field.to_owned()),
+                message,
+            }),
+        }
+    }
+
+    Ok(output)
+}
+
+/// Import cards from a Scryfall bulk data file.
+pub fn import_path(path: impl AsRef<Path>) -> Result<ScryfallImport, ObjectDbError> {
+    let file = File::open(path).map_err(ObjectDbError::Io)?;
+    import(BufReader::new(file))
+}
+
+/// Convert a Scryfall card into a card descriptor, or describe the field that
+/// couldn't be converted.
+fn convert(card: &ScryfallCard) -> Result<CardDescriptor, (&'static str, String)> {
+    // TODO: Cards with more than one face, like split cards, adventurers and
+    // transforming double-faced cards.
+    if card.card_faces.is_some() {
+        return Err((
+            "card_faces",
+            "cards with more than one face aren't supported".to_owned(),
+        ));
+    }
+
+    let type_line = card
+        .type_line
+        .as_deref()
+        .ok_or(("type_line", "this field is required".to_owned()))?;
+    let type_line = parse_type_line(type_line).map_err(|message| ("type_line", message))?;
+
+    let mana_cost = match card.mana_cost.as_deref() {
+        None | Some("") => None,
+        Some(cost) => Some(
+            cost.parse::<ManaCost>()
+                .map_err(|err| ("mana_cost", err.to_string()))?,
+        ),
+    };
+
+    let pt = match (&card.power, &card.toughness) {
+        (Some(power), Some(toughness)) => {
+            let power = parse_pt_value(power).map_err(|message| ("power", message))?;
+            let toughness = parse_pt_value(toughness).map_err(|message| ("toughness", message))?;
+
+            if power.star || toughness.star {
+                Some(PtCharacteristic::Star { power, toughness })
+            } else {
+                Some(PtCharacteristic::Normal(PtValue::new(
+                    power.base,
+                    toughness.base,
+                )))
+            }
+        }
+        _ => None,
+    };
+
+    let image = card
+        .image_uris
+        .as_ref()
+        .and_then(|uris| uris.normal.clone());
+
+    Ok(CardDescriptor {
+        name: Ident::new(&card.name),
+        types: type_line.types,
+        supertypes: type_line.supertypes,
+        subtypes: type_line.subtypes,
+        mana_cost,
+        image,
+        pt,
+        chapter_abilities: Vec::new(),
+        mulligan_abilities: Vec::new(),
+    })
+}
+
+/// 205.1a The type line is printed directly below the illustration. It
+///        contains the card’s card type(s). It also contains the card’s
+///        subtype(s) and supertype(s), if applicable.
+///
+/// Subtypes follow a long dash, like in `Legendary Creature — Elf Druid`.
+fn parse_type_line(type_line: &str) -> Result<TypeLine, String> {
+    let (types_text, subtypes_text) = type_line.split_once('—').unwrap_or((type_line, ""));
+
+    let mut supertypes = Vec::new();
+    let mut types = Vec::new();
+
+    for word in types_text.split_whitespace() {
+        if let Ok(supertype) = CardSupertype::deserialize(name_deserializer(word)) {
+            supertypes.push(supertype);
+        } else if let Ok(card_type) = CardType::deserialize(name_deserializer(word)) {
+            types.push(card_type);
+        } else {
+            return Err(format!("{:?} is not a card type or supertype", word));
+        }
+    }
+
+    if types.is_empty() {
+        return Err(format!("{:?} has no card types", type_line));
+    }
+
+    let subtypes = subtypes_text
+        .split_whitespace()
+        .map(|name| CardSubtype {
+            name: Ident::new(name),
+        })
+        .collect();
+
+    Ok(TypeLine {
+        supertypes,
+        types,
+        subtypes,
+    })
+}
+
+struct TypeLine {
+    supertypes: Vec<CardSupertype>,
+    types: Vec<CardType>,
+    subtypes: Vec<CardSubtype>,
+}
+
+/// Types and supertypes are named the same way in type lines as they are in
+/// card data, so their names can be read with their `Deserialize` impls.
+fn name_deserializer(name: &str) -> StrDeserializer<'_, ValueError> {
+    name.into_deserializer()
+}
+
+/// Parse a printed power or toughness, like `2`, `*` or `1+*`.
+fn parse_pt_value(value: &str) -> Result<StarValue, String> {
+    let (base, star) = match value.strip_suffix('*') {
+        Some("") => ("0", true),
+        Some(base) => (base.strip_suffix('+').unwrap_or(base), true),
+        None => (value, false),
+    };
+
+    let base = base
+        .parse()
+        .map_err(|_| format!("{:?} is not a supported power or toughness", value))?;
+
+    Ok(StarValue { base, star })
+}
+
+/// 207.2a Reminder text is italicized text within parentheses that summarizes
+///        a rule that applies to that card. [...] It has no effect on game
+///        play.
+fn without_reminder_text(text: &str) -> String {
+    let mut output = String::new();
+    let mut depth = 0;
+
+    for c in text.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' if depth > 0 => depth -= 1,
+            _ if depth == 0 => output.push(c),
+            _ => {}
+        }
+    }
+
+    output
+}
@@ -1,7 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-use crate::cost::ManaColor;
-use crate::mana_pool::{Mana, ManaPool};
+use crate::mana_pool::ManaPool;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -21,43 +20,41 @@ pub struct Player {
     pub has_lost: bool,
     pub lands_played_this_turn: u32,
     pub life: i64,
+
+    /// How many mulligans the player has taken before keeping their opening
+    /// hand. See rule 103.4.
+    pub mulligans_taken: u32,
+
     pub mana_pool: ManaPool,
 }
 
 impl Player {
-    pub fn new(id: PlayerId, name: String) -> Self {
-        let mut mana_pool = ManaPool::new();
-        mana_pool.add(Mana {
-            color: ManaColor::Green,
-        });
-        mana_pool.add(Mana {
-            color: ManaColor::Red,
-        });
-        mana_pool.add(Mana {
-            color: ManaColor::Green,
-        });
-
+    pub fn new(id: PlayerId, name: String, life: i64) -> Self {
         Self {
             id,
             name,
             has_drawn_from_empty_library: false,
             has_lost: false,
             lands_played_this_turn: 0,
-            life: 20,
-            mana_pool,
+            life,
+            mulligans_taken: 0,
+            mana_pool: ManaPool::new(),
         }
     }
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Players {
     inner: Vec<Player>,
 }
 
 impl Players {
-    pub fn new(num_players: u32) -> Self {
-        let inner = (0..num_players)
-            .map(|i| Player::new(PlayerId(i), format!("Player {}", i + 1)))
+    /// Create players with the given names, in turn order.
+    pub fn new(names: impl IntoIterator<Item = String>, starting_life: i64) -> Self {
+        let inner = names
+            .into_iter()
+            .enumerate()
+            .map(|(i, name)| Player::new(PlayerId(i as u32), name, starting_life))
             .collect();
 
         Self { inner }
@@ -67,6 +64,10 @@ impl Players {
         self.inner.len()
     }
 
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
     pub fn get(&self, id: PlayerId) -> Option<&Player> {
         self.inner.get(id.0 as usize)
     }
@@ -79,6 +80,19 @@ impl Players {
         self.inner.iter()
     }
 
+    /// Players that haven't lost or otherwise left the game, in turn order.
+    pub fn in_game(&self) -> impl Iterator<Item = &'_ Player> + '_ {
+        self.inner.iter().filter(|player| !player.has_lost)
+    }
+
+    /// Whether the player is part of the game and hasn't left it.
+    pub fn is_in_game(&self, id: PlayerId) -> bool {
+        self.get(id).is_some_and(|player| !player.has_lost)
+    }
+
+    /// The next player in turn order who is still in the game. The given player
+    /// may have left the game already. If nobody else is left, returns the
+    /// given player.
     pub fn player_after(&self, id: PlayerId) -> PlayerId {
         let maybe_index = self.inner.iter().position(|turn| turn.id == id);
 
@@ -87,8 +101,10 @@ impl Players {
             None => panic!("Game::player_after was called with a non-player Entity."),
         };
 
-        let next_index = (index + 1) % self.inner.len();
-        self.inner[next_index].id
+        (1..self.inner.len())
+            .map(|offset| &self.inner[(index + offset) % self.inner.len()])
+            .find(|player| !player.has_lost)
+            .map_or(id, |player| player.id)
     }
 }
 
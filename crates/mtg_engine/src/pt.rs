@@ -30,6 +30,13 @@ impl fmt::Display for PtValue {
 #[derive(Debug, Clone, Copy, Serialize, Deserialize)]
 pub enum PtCharacteristic {
     Normal(PtValue),
+
+    /// 208.2. Rather than a fixed number, some creature cards have power
+    ///        and/or toughness that includes a star (*).
+    Star {
+        power: StarValue,
+        toughness: StarValue,
+    },
 }
 
 impl PtCharacteristic {
@@ -39,14 +46,38 @@ impl PtCharacteristic {
     pub fn resolve(&self) -> PtValue {
         match self {
             Self::Normal(value) => *value,
+            Self::Star { power, toughness } => PtValue::new(power.resolve(), toughness.resolve()),
         }
     }
 }
 
+/// A printed power or toughness that may include a star, like the `1+*` in
+/// Tarmogoyf's `*/1+*`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct StarValue {
+    /// The number printed alongside the star, if any.
+    pub base: i64,
+
+    /// Whether the value includes a star.
+    pub star: bool,
+}
+
+impl StarValue {
+    /// 208.2a [...] If the ability needs to use a number that can’t be
+    ///        determined, including inside a calculation, use 0 instead of
+    ///        that number.
+    ///
+    /// TODO: Characteristic-defining abilities that say what the star is.
+    pub fn resolve(&self) -> i64 {
+        self.base
+    }
+}
+
 /// An effect that sets the power/toughness of a creature directly, like Lignify
 /// or March of the Machines.
 ///
 /// Applies in layer 7B.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SetPtEffect {
     // TODO: Change to selector type
     pub target: Entity,
@@ -59,6 +90,7 @@ pub struct SetPtEffect {
 /// setting it, like Giant Growth or Shared Triumph.
 ///
 /// Applies in layer 7C.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AdjustPtEffect {
     // TODO: Change to selector type
     pub target: Entity,
@@ -71,6 +103,7 @@ pub struct AdjustPtEffect {
 /// Out or Merfolk Thaumaturgist.
 ///
 /// Applies in layer 7E.
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SwitchPtEffect {
     // TODO: Change to selector type
     pub target: Entity,
@@ -4,9 +4,10 @@ use std::mem::swap;
 
 use hecs::Entity;
 
-use crate::components::{Counters, Object, Permanent};
+use crate::components::{Counters, Object, Permanent, Timestamp};
 use crate::counters::Counter;
 use crate::game::Game;
+use crate::hand_size::{MaxHandSizeEffect, MaxHandSizeModification, DEFAULT_MAX_HAND_SIZE};
 use crate::player::PlayerId;
 use crate::pt::{AdjustPtEffect, PtValue, SetPtEffect, SwitchPtEffect};
 use crate::types::CardType;
@@ -38,19 +39,19 @@ impl Query for QueryPt {
         // Layer 7a: characteristic-defining P/T.
         let mut calculated_pt = object.pt?.resolve();
 
-        // Layer 7b: any effects that directly set power/toughness.
-        //
-        // TODO: Sort by timestamp.
-        let mut layer_7b_query = game.world().query::<(&SetPtEffect,)>();
-        for (_entity, (effect,)) in layer_7b_query.iter() {
-            if effect.target == self.0 {
-                calculated_pt = effect.value;
-            }
+        // Layer 7b: any effects that directly set power/toughness. The one
+        // with the latest timestamp is applied last.
+        let mut layer_7b_query = game.world().query::<(&SetPtEffect, Option<&Timestamp>)>();
+        let latest = layer_7b_query
+            .iter()
+            .filter(|(_entity, (effect, _))| effect.target == self.0)
+            .max_by_key(|(_entity, (_, timestamp))| timestamp.copied());
+        if let Some((_entity, (effect, _))) = latest {
+            calculated_pt = effect.value;
         }
 
         // Layer 7c: any effects that adjust power/toughness without setting it.
-        //
-        // TODO: Sort by timestamp.
+        // Adjustments add up the same in any order.
         let mut layer_7c_query = game.world().query::<(&AdjustPtEffect,)>();
         for (_entity, (effect,)) in layer_7c_query.iter() {
             if effect.target == self.0 {
@@ -81,14 +82,44 @@ impl Query for QueryPt {
     }
 }
 
+/// Query the maximum hand size of a player, returning `None` if the player has
+/// no maximum hand size.
 pub struct QueryMaxHandSize(pub PlayerId);
 
 impl Query for QueryMaxHandSize {
-    type Output = i64;
+    type Output = Option<i64>;
+
+    fn query(&self, game: &Game) -> Self::Output {
+        let mut no_maximum = false;
+        let mut max_hand_size = DEFAULT_MAX_HAND_SIZE;
+        let mut adjustment = 0;
+
+        // Effects that set the maximum hand size apply before effects that
+        // adjust it, similar to layers 7b and 7c for power and toughness, and
+        // in timestamp order among themselves.
+        let mut query = game
+            .world()
+            .query::<(&MaxHandSizeEffect, Option<&Timestamp>)>();
+        let mut effects: Vec<_> = query
+            .iter()
+            .map(|(_entity, (effect, timestamp))| (timestamp.copied(), effect))
+            .filter(|(_, effect)| effect.player == self.0)
+            .collect();
+        effects.sort_by_key(|&(timestamp, _)| timestamp);
+
+        for (_, effect) in effects {
+            match effect.modification {
+                MaxHandSizeModification::NoMaximum => no_maximum = true,
+                MaxHandSizeModification::Set(value) => max_hand_size = value,
+                MaxHandSizeModification::Adjust(value) => adjustment += value,
+            }
+        }
+
+        if no_maximum {
+            return None;
+        }
 
-    fn query(&self, _game: &Game) -> Self::Output {
-        // TODO: Look for affects that alter maximum hand size.
-        7
+        Some((max_hand_size + adjustment).max(0))
     }
 }
 
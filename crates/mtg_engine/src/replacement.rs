@@ -0,0 +1,121 @@
+//! 614. Replacement Effects
+//!
+//! 614.1. Some continuous effects are replacement effects. Like prevention
+//!        effects (see rule 615), replacement effects apply continuously as
+//!        events happen—they aren’t locked in ahead of time. Such effects
+//!        watch for a particular event that would happen and completely or
+//!        partially replace that event with a different event. They act like
+//!        “shields” around whatever they’re affecting.
+
+use hecs::Entity;
+use serde::{Deserialize, Serialize};
+
+use crate::effect::Effect;
+use crate::player::PlayerId;
+use crate::target::Target;
+use crate::zone::ZoneId;
+
+/// An event that replacement effects are able to modify or replace before it
+/// happens.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ReplaceableEvent {
+    /// A player draws a single card.
+    Draw { player: PlayerId },
+
+    /// An object moves from one zone to another. `tapped` is only meaningful
+    /// when the object is entering the battlefield.
+    ZoneChange {
+        object: Entity,
+        to: ZoneId,
+        tapped: bool,
+    },
+
+    /// 120.1. Objects can deal damage to creatures, planeswalkers, and
+    ///        players.
+    Damage {
+        source: Entity,
+        target: Target,
+        amount: u64,
+
+        /// 510.2. [...] all combat damage that’s been assigned is dealt
+        ///        simultaneously.
+        combat: bool,
+    },
+
+    /// 701.7a To destroy a permanent, move it from the battlefield to its
+    ///        owner’s graveyard.
+    Destroy { permanent: Entity },
+}
+
+impl ReplaceableEvent {
+    /// The object that the event happens to, if it happens to an object rather
+    /// than a player.
+    pub fn affected_object(&self) -> Option<Entity> {
+        match self {
+            ReplaceableEvent::Draw { .. }
+            | ReplaceableEvent::Damage {
+                target: Target::Player(_),
+                ..
+            } => None,
+            ReplaceableEvent::ZoneChange { object, .. }
+            | ReplaceableEvent::Destroy { permanent: object }
+            | ReplaceableEvent::Damage {
+                target: Target::Object(object),
+                ..
+            } => Some(*object),
+        }
+    }
+}
+
+/// A continuous effect that modifies or replaces events as they happen. These
+/// are spawned into the world as components, just like other effects.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type")]
+pub enum ReplacementEffect {
+    /// 614.1d Continuous effects that read “[This permanent] enters the
+    ///        battlefield . . .” or “[Objects] enter the battlefield . . .”
+    ///        are replacement effects.
+    ///
+    /// Permanents that the given player would control enter the battlefield
+    /// tapped, like Frozen Aether.
+    EntersTapped {
+        // TODO: Change to selector type
+        player: PlayerId,
+    },
+
+    /// 614.1a Effects that use the word “instead” are replacement effects.
+    ///
+    /// If the given player would draw a card, the controller of this effect
+    /// performs the given effects instead.
+    DrawInstead {
+        player: PlayerId,
+        controller: PlayerId,
+        effects: Vec<Effect>,
+    },
+
+    /// If a card would be put into a graveyard, exile it instead, like Rest in
+    /// Peace. If `player` is set, only cards going to that player's graveyard
+    /// are affected, like Leyline of the Void.
+    ExileInsteadOfGraveyard { player: Option<PlayerId> },
+
+    /// 615.7. Some prevention effects generate a prevention shield. [...] Each
+    ///        1 damage that’s prevented reduces the remaining shield by 1.
+    ///        When the shield is reduced to 0, the effect ends.
+    ///
+    /// Prevents the next `remaining` damage that would be dealt to the target.
+    PreventDamage { target: Target, remaining: u64 },
+
+    /// Prevent all combat damage that would be dealt, like Fog. Usually
+    /// spawned along with `UntilEotEffect`.
+    PreventAllCombatDamage,
+
+    /// 701.15a If the effect of a resolving spell or ability regenerates a
+    ///         permanent, it creates a replacement effect that protects the
+    ///         permanent the next time it would be destroyed this turn. In
+    ///         this case, “Regenerate [permanent]” means “The next time
+    ///         [permanent] would be destroyed this turn, instead remove all
+    ///         damage marked on it and its controller taps it. If it’s an
+    ///         attacking or blocking creature, remove it from combat.”
+    Regenerate { permanent: Entity },
+}
@@ -34,6 +34,22 @@ pub enum CardType {
     Vanguard,
 }
 
+impl CardType {
+    /// 110.4. There are five permanent types: artifact, creature, enchantment,
+    ///        land, and planeswalker. Instant and sorcery cards can’t enter the
+    ///        battlefield and thus can’t be permanents.
+    pub fn is_permanent_type(&self) -> bool {
+        matches!(
+            self,
+            CardType::Artifact
+                | CardType::Creature
+                | CardType::Enchantment
+                | CardType::Land
+                | CardType::Planeswalker
+        )
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(transparent)]
 pub struct CardSubtype {
@@ -1,5 +1,6 @@
 use hecs::Entity;
 use rand::seq::SliceRandom;
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::player::PlayerId;
@@ -9,7 +10,7 @@ use crate::player::PlayerId;
 ///        exile, and command. Some older cards also use the ante zone. Each
 ///        player has their own library, hand, and graveyard. The other zones
 ///        are shared by all players.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub enum ZoneId {
     Library(PlayerId),
     Hand(PlayerId),
@@ -20,7 +21,8 @@ pub enum ZoneId {
     Command,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
 pub struct Zone {
     members: Vec<Entity>,
 }
@@ -32,10 +34,6 @@ impl Zone {
         }
     }
 
-    pub(crate) fn clear(&mut self) {
-        self.members.clear();
-    }
-
     pub(crate) fn add(&mut self, entity: Entity) {
         self.members.push(entity);
     }
@@ -44,9 +42,15 @@ impl Zone {
         self.members.retain(|e| *e != entity);
     }
 
-    pub fn shuffle(&mut self) {
-        let mut rng = rand::thread_rng();
-        self.members.shuffle(&mut rng);
+    /// Move a member to the bottom of the zone, which is the start of its
+    /// members. The top of a library is its last member.
+    pub(crate) fn move_to_bottom(&mut self, entity: Entity) {
+        self.remove(entity);
+        self.members.insert(0, entity);
+    }
+
+    pub(crate) fn shuffle<R: Rng>(&mut self, rng: &mut R) {
+        self.members.shuffle(rng);
     }
 
     pub fn is_empty(&self) -> bool {